@@ -0,0 +1,242 @@
+use bevy::prelude::*;
+use bevy::render::render_resource::{Extent3d, TextureDimension};
+use bevy::ui::FocusPolicy;
+
+use crate::camera::WINDOW_SCALE;
+use crate::types::{GameState, ImageAssets};
+
+/// Width and height of every glyph in world pixels. On the UI each one covers
+/// `GLYPH_SIZE * WINDOW_SCALE` screen pixels, like the icons next to it.
+pub const GLYPH_SIZE: f32 = 8.;
+
+const GLYPH_PIXELS: usize = 8;
+
+/// Bytes per pixel of the sheet, which is loaded as RGBA with 8 bits per channel.
+const PIXEL_BYTES: usize = 4;
+
+/// The light grey the glyphs are drawn in on `alphanumeric.png`.
+pub const TEXT_COLOR: Color = Color::rgb(0.678, 0.706, 0.749);
+
+/// Scale of menu titles and other headings.
+pub const HEADING_SCALE: f32 = 2.;
+
+/// Characters in the order of the tiles on `alphanumeric.png`, left to right and top to bottom.
+/// Spaces mark frame pieces and symbols that nothing types.
+const GLYPHS: &str = concat!(
+    "ABCDEFGHIJKLM.!?",
+    "NOPQRSTUVWXYZ,:;",
+    "abcdefghijklm\"' ",
+    "nopqrstuvwxyz /\\",
+    "01234#$ ~       ",
+    "56789&@%^       ",
+    "+-* =<>()_      ",
+);
+
+/// One image per tile of `alphanumeric.png`, cut out once the sheet has loaded. Separate images
+/// work both for sprites and for UI nodes, which can't show part of a texture atlas.
+pub struct BitmapFont {
+    glyphs: Vec<Handle<Image>>,
+}
+
+impl BitmapFont {
+    /// Characters the sheet doesn't have are drawn as `?`, spaces aren't drawn at all.
+    fn glyph(&self, character: char) -> Option<Handle<Image>> {
+        if character == ' ' {
+            return None;
+        }
+
+        GLYPHS
+            .chars()
+            .position(|glyph| glyph == character)
+            .or_else(|| GLYPHS.find('?'))
+            .and_then(|index| self.glyphs.get(index))
+            .cloned()
+    }
+}
+
+/// A line of text drawn with `BitmapFont`. On an entity with a `Node` the glyphs are laid out
+/// as UI images, otherwise as sprites centered on the entity. The glyphs are its children and
+/// are replaced whenever the text changes, so the entity shouldn't have any other children.
+#[derive(Component, Clone, Debug)]
+pub struct BitmapText {
+    pub value: String,
+    pub color: Color,
+    /// Glyphs are drawn this many times their size, whole numbers keep them crisp
+    pub scale: f32,
+}
+
+impl BitmapText {
+    pub fn new(value: impl Into<String>) -> Self {
+        Self {
+            value: value.into(),
+            color: TEXT_COLOR,
+            scale: 1.,
+        }
+    }
+
+    pub fn with_color(mut self, color: Color) -> Self {
+        self.color = color;
+        self
+    }
+
+    pub fn with_scale(mut self, scale: f32) -> Self {
+        self.scale = scale;
+        self
+    }
+}
+
+/// A line of `BitmapText` on the UI. Clicks go through it to the buttons it is drawn on.
+#[derive(Bundle, Clone, Debug)]
+pub struct BitmapTextBundle {
+    #[bundle]
+    pub node: NodeBundle,
+    pub text: BitmapText,
+}
+
+impl BitmapTextBundle {
+    pub fn new(text: BitmapText) -> Self {
+        Self {
+            node: NodeBundle {
+                color: Color::NONE.into(),
+                focus_policy: FocusPolicy::Pass,
+                ..Default::default()
+            },
+            text,
+        }
+    }
+}
+
+pub struct BitmapTextPlugin;
+
+impl Plugin for BitmapTextPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(SystemSet::on_exit(GameState::Loading).with_system(setup))
+            .add_system(layout_bitmap_text);
+    }
+}
+
+fn setup(
+    mut commands: Commands,
+    image_assets: Res<ImageAssets>,
+    mut images: ResMut<Assets<Image>>,
+) {
+    let sheet = match images.get(&image_assets.alphanumeric) {
+        Some(sheet) => sheet.clone(),
+        None => return,
+    };
+
+    commands.insert_resource(BitmapFont {
+        glyphs: cut_tiles(&sheet, &mut images),
+    });
+}
+
+/// Cuts an Oracle sheet into one image per 8x8 tile, left to right and top to bottom. The sheets
+/// are drawn on an opaque background, which becomes transparent, and everything else white so
+/// the tiles take on whatever colour they are drawn in.
+pub fn cut_tiles(sheet: &Image, images: &mut Assets<Image>) -> Vec<Handle<Image>> {
+    let sheet_width = sheet.texture_descriptor.size.width as usize;
+    let columns = sheet_width / GLYPH_PIXELS;
+    let rows = sheet.texture_descriptor.size.height as usize / GLYPH_PIXELS;
+
+    // The top left pixel is always part of the background
+    let background = &sheet.data[..PIXEL_BYTES];
+
+    (0..columns * rows)
+        .map(|index| {
+            let (column, row) = (index % columns, index / columns);
+
+            let mut data = Vec::with_capacity(GLYPH_PIXELS * GLYPH_PIXELS * PIXEL_BYTES);
+            for y in 0..GLYPH_PIXELS {
+                let start =
+                    ((row * GLYPH_PIXELS + y) * sheet_width + column * GLYPH_PIXELS) * PIXEL_BYTES;
+                let line = &sheet.data[start..start + GLYPH_PIXELS * PIXEL_BYTES];
+
+                for pixel in line.chunks(PIXEL_BYTES) {
+                    if pixel == background {
+                        data.extend_from_slice(&[0, 0, 0, 0]);
+                    } else {
+                        data.extend_from_slice(&[255, 255, 255, 255]);
+                    }
+                }
+            }
+
+            images.add(Image::new(
+                Extent3d {
+                    width: GLYPH_PIXELS as u32,
+                    height: GLYPH_PIXELS as u32,
+                    depth_or_array_layers: 1,
+                },
+                TextureDimension::D2,
+                data,
+                sheet.texture_descriptor.format,
+            ))
+        })
+        .collect()
+}
+
+fn layout_bitmap_text(
+    mut commands: Commands,
+    font: Option<Res<BitmapFont>>,
+    query: Query<(Entity, &BitmapText, Option<&Node>, Option<&Children>), Changed<BitmapText>>,
+) {
+    let font = match font {
+        Some(font) => font,
+        None => return,
+    };
+
+    for (entity, text, node, children) in query.iter() {
+        for child in children.iter().flat_map(|children| children.iter()) {
+            commands.entity(*child).despawn_recursive();
+        }
+
+        let length = text.value.chars().count();
+        commands.entity(entity).with_children(|parent| {
+            for (index, character) in text.value.chars().enumerate() {
+                let glyph = font.glyph(character);
+
+                if node.is_some() {
+                    let size = Size::new(
+                        Val::Px(GLYPH_SIZE * WINDOW_SCALE * text.scale),
+                        Val::Px(GLYPH_SIZE * WINDOW_SCALE * text.scale),
+                    );
+
+                    // Spaces still take up room between the glyphs around them
+                    match glyph {
+                        Some(glyph) => parent.spawn_bundle(ImageBundle {
+                            style: Style {
+                                size,
+                                ..Default::default()
+                            },
+                            image: glyph.into(),
+                            color: text.color.into(),
+                            focus_policy: FocusPolicy::Pass,
+                            ..Default::default()
+                        }),
+                        None => parent.spawn_bundle(NodeBundle {
+                            style: Style {
+                                size,
+                                ..Default::default()
+                            },
+                            color: Color::NONE.into(),
+                            focus_policy: FocusPolicy::Pass,
+                            ..Default::default()
+                        }),
+                    };
+                } else if let Some(glyph) = glyph {
+                    let x = (index as f32 - (length as f32 - 1.) / 2.) * GLYPH_SIZE * text.scale;
+
+                    parent.spawn_bundle(SpriteBundle {
+                        texture: glyph,
+                        sprite: Sprite {
+                            color: text.color,
+                            custom_size: Some(Vec2::splat(GLYPH_SIZE * text.scale)),
+                            ..Default::default()
+                        },
+                        transform: Transform::from_xyz(x, 0., 0.),
+                        ..Default::default()
+                    });
+                }
+            }
+        });
+    }
+}
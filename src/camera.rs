@@ -1,60 +1,186 @@
 use bevy::prelude::*;
+use bevy_ecs_ldtk::prelude::*;
+use heron::Velocity;
 
 #[derive(Component, Default)]
 pub struct CameraFollowing;
 
 #[derive(Component, Default)]
-pub struct CameraThatFollows;
+pub struct CameraThatFollows {
+    /// Where the camera is headed before shaking, so the shake never feeds into the smoothing
+    focus: Vec2,
+    /// How much the camera shakes, from 0 to 1
+    trauma: f32,
+}
+
+/// The world-space area the camera may show, on the entity of each spawned level.
+#[derive(Component, Clone, Copy, Debug)]
+pub struct CameraBounds {
+    pub min: Vec2,
+    pub max: Vec2,
+}
+
+impl CameraBounds {
+    fn contains(&self, point: Vec2) -> bool {
+        point.cmpge(self.min).all() && point.cmple(self.max).all()
+    }
+}
+
+/// Shakes the camera. Strengths add up to a maximum of 1, which shakes by `max_shake_offset`.
+#[derive(Clone, Copy, Debug)]
+pub struct ScreenShake(pub f32);
+
+pub struct CameraSettings {
+    /// How quickly the camera catches up with the player, higher is snappier
+    pub damping: f32,
+    /// The camera leads the player by where they will be this far ahead
+    pub look_ahead_seconds: f32,
+    pub max_look_ahead: f32,
+    /// Pixels the camera moves at most while shaking
+    pub max_shake_offset: f32,
+    /// Trauma lost per second
+    pub shake_decay: f32,
+    /// `ScreenShake` events are ignored while this is off
+    pub screen_shake: bool,
+}
+
+impl Default for CameraSettings {
+    fn default() -> Self {
+        Self {
+            damping: 6.,
+            look_ahead_seconds: 0.25,
+            max_look_ahead: 24.,
+            max_shake_offset: 4.,
+            shake_decay: 1.5,
+            screen_shake: true,
+        }
+    }
+}
 
 pub struct CameraPlugin;
 
+/// How many screen pixels each world pixel covers in a window of the default size.
 pub const WINDOW_SCALE: f32 = 4.;
 
-pub const DEFAULT_PROJECTION: f32 = 1. / WINDOW_SCALE;
-
 impl Plugin for CameraPlugin {
     fn build(&self, app: &mut App) {
-        app.add_startup_system(setup)
-            .add_system(toggle_projection)
+        app.init_resource::<CameraSettings>()
+            .add_event::<ScreenShake>()
+            .add_startup_system(setup)
+            .add_system(add_level_bounds)
             .add_system_to_stage(CoreStage::Update, camera_follow_player);
     }
 }
 
+/// The camera following the player draws into the low resolution target of `PixelPerfectPlugin`.
 fn setup(mut commands: Commands) {
-    let mut camera = OrthographicCameraBundle::new_2d();
-
-    // Set the scale on the window right away
-    camera.orthographic_projection.scale = DEFAULT_PROJECTION;
-
-    commands
-        .spawn_bundle(camera)
-        .insert(CameraThatFollows::default());
-
     commands.spawn_bundle(UiCameraBundle::default());
 }
 
-fn toggle_projection(
-    keyboard_input: Res<Input<KeyCode>>,
-    mut query: Query<&mut OrthographicProjection>,
+/// Levels are spawned with their bottom left corner at their translation.
+fn add_level_bounds(
+    mut commands: Commands,
+    level_assets: Res<Assets<LdtkLevel>>,
+    query: Query<(Entity, &Handle<LdtkLevel>, &Transform), Without<CameraBounds>>,
 ) {
-    if keyboard_input.just_released(KeyCode::M) {
-        for mut projection in query.iter_mut() {
-            projection.scale = if projection.scale == 1. {
-                DEFAULT_PROJECTION
-            } else {
-                1.
-            }
+    for (entity, handle, transform) in query.iter() {
+        if let Some(ldtk_level) = level_assets.get(handle) {
+            let min = transform.translation.truncate();
+            let size = Vec2::new(
+                ldtk_level.level.px_wid as f32,
+                ldtk_level.level.px_hei as f32,
+            );
+
+            commands.entity(entity).insert(CameraBounds {
+                min,
+                max: min + size,
+            });
         }
     }
 }
 
 pub fn camera_follow_player(
-    player_query: Query<&Transform, (With<CameraFollowing>, Without<CameraThatFollows>)>,
-    mut camera_query: Query<&mut Transform, With<CameraThatFollows>>,
+    time: Res<Time>,
+    settings: Res<CameraSettings>,
+    mut screen_shake_events: EventReader<ScreenShake>,
+    player_query: Query<
+        (
+            &Transform,
+            Option<&Velocity>,
+            ChangeTrackers<CameraFollowing>,
+        ),
+        Without<CameraThatFollows>,
+    >,
+    bounds_query: Query<&CameraBounds>,
+    mut camera_query: Query<(
+        &mut Transform,
+        &mut CameraThatFollows,
+        &OrthographicProjection,
+    )>,
 ) {
-    if let Ok(player_transform) = player_query.get_single() {
-        if let Ok(mut camera_transform) = camera_query.get_single_mut() {
-            camera_transform.translation = player_transform.translation;
+    let (mut camera_transform, mut camera, projection) = match camera_query.get_single_mut() {
+        Ok(camera) => camera,
+        Err(_) => return,
+    };
+
+    let delta = time.delta_seconds();
+    for ScreenShake(strength) in screen_shake_events.iter() {
+        if settings.screen_shake {
+            camera.trauma = (camera.trauma + strength).min(1.);
         }
     }
+    camera.trauma = (camera.trauma - settings.shake_decay * delta).max(0.);
+
+    let (player_transform, velocity, following) = match player_query.get_single() {
+        Ok(player) => player,
+        Err(_) => return,
+    };
+
+    let position = player_transform.translation.truncate();
+    let look_ahead = velocity
+        .map(|velocity| velocity.linear.truncate() * settings.look_ahead_seconds)
+        .unwrap_or_default()
+        .clamp_length_max(settings.max_look_ahead);
+    let target = position + look_ahead;
+
+    // Jump straight to a player that was just spawned instead of panning across the level
+    camera.focus = if following.is_added() {
+        target
+    } else {
+        camera
+            .focus
+            .lerp(target, 1. - (-settings.damping * delta).exp())
+    };
+
+    let half_view = Vec2::new(projection.right, projection.top) * projection.scale;
+    if let Some(bounds) = bounds_query.iter().find(|bounds| bounds.contains(position)) {
+        camera.focus = clamp_to_bounds(camera.focus, half_view, bounds);
+    }
+
+    // Squaring keeps small shakes subtle
+    let shake = camera.trauma * camera.trauma * settings.max_shake_offset;
+    let offset = Vec2::new(
+        rand::random::<f32>() * 2. - 1.,
+        rand::random::<f32>() * 2. - 1.,
+    ) * shake;
+
+    // Whole pixels only, anything in between makes the sprites shimmer as the camera moves
+    let z = camera_transform.translation.z;
+    camera_transform.translation = (camera.focus + offset).round().extend(z);
+}
+
+/// Keeps the view inside the bounds, centering it on levels smaller than the view.
+fn clamp_to_bounds(focus: Vec2, half_view: Vec2, bounds: &CameraBounds) -> Vec2 {
+    let clamp_axis = |focus: f32, half_view: f32, min: f32, max: f32| {
+        if max - min <= half_view * 2. {
+            (min + max) / 2.
+        } else {
+            focus.clamp(min + half_view, max - half_view)
+        }
+    };
+
+    Vec2::new(
+        clamp_axis(focus.x, half_view.x, bounds.min.x, bounds.max.x),
+        clamp_axis(focus.y, half_view.y, bounds.min.y, bounds.max.y),
+    )
 }
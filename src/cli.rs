@@ -0,0 +1,140 @@
+use std::path::PathBuf;
+
+use bevy::prelude::KeyCode;
+
+/// Options passed to the native binary on the command line.
+#[derive(Clone, Debug, Default)]
+pub struct CliOptions {
+    /// Run the game without a window for this many frames and print the final state.
+    pub headless_frames: Option<usize>,
+    /// Keys held down for the whole headless run.
+    pub headless_keys: Vec<KeyCode>,
+    /// Record every run to this replay file.
+    pub record: Option<PathBuf>,
+    /// Play back this replay file instead of reading the player's input.
+    pub replay: Option<PathBuf>,
+    /// Skip the menus and start a run on this level, given by number, identifier or name.
+    pub level: Option<String>,
+    /// Skip the menus and start an endless run with this seed.
+    pub seed: Option<u32>,
+    /// Check `levels.ldtk` for mistakes and exit, with a non-zero code if any were found.
+    pub validate_levels: bool,
+}
+
+impl CliOptions {
+    /// Exits with code 2 when an option is missing its value or the value is malformed, such as
+    /// `--headless` without a number, which would otherwise open a window.
+    pub fn from_args() -> Self {
+        match Self::parse(std::env::args().skip(1)) {
+            Ok(options) => options,
+            Err(message) => {
+                eprintln!("{}", message);
+                std::process::exit(2);
+            }
+        }
+    }
+
+    fn parse(args: impl Iterator<Item = String>) -> Result<Self, String> {
+        let mut options = Self::default();
+        let mut args = args.peekable();
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--headless" => {
+                    let frames = args.next().and_then(|frames| frames.parse().ok());
+                    match frames {
+                        Some(frames) => options.headless_frames = Some(frames),
+                        None => return Err("--headless expects a number of frames".to_string()),
+                    }
+                }
+                "--hold" => {
+                    while let Some(key) = args.peek().and_then(|key| key_code_from_name(key)) {
+                        options.headless_keys.push(key);
+                        args.next();
+                    }
+                }
+                "--record" => match args.next() {
+                    Some(path) => options.record = Some(PathBuf::from(path)),
+                    None => {
+                        return Err("--record expects a file to write the replay to".to_string())
+                    }
+                },
+                "--replay" => match args.next() {
+                    Some(path) => options.replay = Some(PathBuf::from(path)),
+                    None => return Err("--replay expects a replay file".to_string()),
+                },
+                "--level" => match args.next() {
+                    Some(level) => options.level = Some(level),
+                    None => return Err("--level expects a level number or name".to_string()),
+                },
+                "--seed" => {
+                    let seed = args.next().and_then(|seed| seed.parse().ok());
+                    match seed {
+                        Some(seed) => options.seed = Some(seed),
+                        None => return Err("--seed expects a number".to_string()),
+                    }
+                }
+                "--validate-levels" => options.validate_levels = true,
+                _ => eprintln!("Ignoring unknown argument {}", arg),
+            }
+        }
+
+        Ok(options)
+    }
+}
+
+fn key_code_from_name(name: &str) -> Option<KeyCode> {
+    match name.to_lowercase().as_str() {
+        "w" => Some(KeyCode::W),
+        "a" => Some(KeyCode::A),
+        "s" => Some(KeyCode::S),
+        "d" => Some(KeyCode::D),
+        "up" => Some(KeyCode::Up),
+        "down" => Some(KeyCode::Down),
+        "left" => Some(KeyCode::Left),
+        "right" => Some(KeyCode::Right),
+        "space" => Some(KeyCode::Space),
+        "enter" | "return" => Some(KeyCode::Return),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::CliOptions;
+
+    fn parse(args: &[&str]) -> Result<CliOptions, String> {
+        CliOptions::parse(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn reads_every_option() {
+        let options = parse(&[
+            "--level",
+            "Shaman Grounds",
+            "--seed",
+            "1234567",
+            "--record",
+            "run.replay",
+            "--validate-levels",
+        ])
+        .unwrap();
+
+        assert_eq!(options.level.as_deref(), Some("Shaman Grounds"));
+        assert_eq!(options.seed, Some(1234567));
+        assert_eq!(options.record, Some("run.replay".into()));
+        assert!(options.validate_levels);
+    }
+
+    #[test]
+    fn rejects_a_seed_that_is_not_a_number() {
+        assert!(parse(&["--seed", "abc"]).is_err());
+    }
+
+    #[test]
+    fn rejects_options_missing_their_value() {
+        for option in ["--headless", "--record", "--replay", "--level", "--seed"] {
+            assert!(parse(&[option]).is_err(), "{} was accepted", option);
+        }
+    }
+}
@@ -0,0 +1,73 @@
+use bevy::prelude::*;
+
+use crate::{
+    bitmap_text::{BitmapText, BitmapTextBundle, HEADING_SCALE},
+    input::Action,
+    types::GameState,
+};
+
+const CREDITS: [&str; 5] = [
+    "Made by dustyplant",
+    "Built with Bevy",
+    "Sprites and font from the",
+    "Oracle 1-bit asset pack",
+    "Debug font: Consola Mono",
+];
+
+#[derive(Component, Default, Clone, Debug)]
+pub struct OnlyInCredits;
+
+pub struct CreditsPlugin;
+
+impl Plugin for CreditsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(SystemSet::on_enter(GameState::Credits).with_system(spawn_ui))
+            .add_system_set(SystemSet::on_update(GameState::Credits).with_system(go_back))
+            .add_system_set(SystemSet::on_exit(GameState::Credits).with_system(cleanup));
+    }
+}
+
+fn spawn_ui(mut commands: Commands) {
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                position_type: PositionType::Absolute,
+                flex_direction: FlexDirection::ColumnReverse,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..Default::default()
+            },
+            color: Color::rgb(0.098, 0.078, 0.169).into(),
+            ..Default::default()
+        })
+        .insert(OnlyInCredits)
+        .with_children(|parent| {
+            parent.spawn_bundle(BitmapTextBundle::new(
+                BitmapText::new("Credits")
+                    .with_color(Color::WHITE)
+                    .with_scale(HEADING_SCALE),
+            ));
+
+            // A space keeps the height of the empty line before the hint
+            for line in CREDITS.into_iter().chain([" ", "Press Enter to go back"]) {
+                parent.spawn_bundle(BitmapTextBundle::new(
+                    BitmapText::new(line).with_color(Color::WHITE),
+                ));
+            }
+        });
+}
+
+fn go_back(mut actions: ResMut<Input<Action>>, mut game_state: ResMut<State<GameState>>) {
+    if actions.just_released(Action::Confirm) || actions.just_released(Action::Pause) {
+        // The main menu resumes this frame, so don't let it pick an entry with the same press
+        actions.clear_just_released(Action::Confirm);
+        game_state.pop().unwrap();
+    }
+}
+
+fn cleanup(mut commands: Commands, query: Query<Entity, With<OnlyInCredits>>) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
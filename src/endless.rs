@@ -0,0 +1,253 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    camera::CameraBounds,
+    game::{
+        components::GridPosition,
+        enemy::components::EnemyArchetype,
+        game::GameWorldState,
+        level::{
+            components::{ColliderBundle, EnemyBundle, PickupBundle, PlayerBundle},
+            generator::{
+                generate_floor, Floor, FloorEntity, COIN_SPRITE, PLAYER_SPRITE, STAIRS_SPRITE,
+            },
+            walls::{WallColliderBundle, GRID_SIZE},
+        },
+        pickup::{Pickup, PickupEffect},
+    },
+    levels::{LevelState, RestartLevel},
+    save::{self, SaveGame},
+    types::{GameState, ImageAssets},
+};
+
+const WALL_Z: f32 = 0.;
+
+const ENTITY_Z: f32 = 2.;
+
+/// Present while the run plays generated floors instead of the levels in `levels.ldtk`. The
+/// current level in `LevelState` is then the depth, counting from 0.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct EndlessRun {
+    pub seed: u32,
+}
+
+/// The seed passed with `--seed`, started as soon as the main menu is up.
+#[derive(Clone, Copy, Debug)]
+pub struct StartEndlessRun(pub u32);
+
+/// Parent of everything spawned for a generated floor.
+#[derive(Component, Clone, Debug)]
+pub struct EndlessFloor {
+    depth: usize,
+}
+
+struct FloorTextures {
+    atlas: Handle<TextureAtlas>,
+}
+
+pub struct EndlessPlugin;
+
+impl Plugin for EndlessPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(SystemSet::on_exit(GameState::Loading).with_system(setup))
+            .add_system_set(SystemSet::on_enter(GameState::MainMenu).with_system(end_endless_run))
+            .add_system_set(
+                SystemSet::on_update(GameState::MainMenu).with_system(start_endless_argument),
+            )
+            .add_system_set(
+                SystemSet::on_update(GameState::InGame)
+                    .with_system(spawn_floor.after("change_level")),
+            )
+            .add_system_set(SystemSet::on_exit(GameState::InGame).with_system(cleanup));
+    }
+}
+
+/// Starts a fresh endless run on its first floor.
+pub fn start_endless_run(commands: &mut Commands, seed: u32) {
+    save::continue_run(
+        commands,
+        SaveGame {
+            version: save::SAVE_VERSION,
+            current_level: 0,
+            world: GameWorldState::default(),
+            endless: Some(EndlessRun { seed }),
+        },
+    );
+}
+
+/// A seed for a run started from the menu. Nobody presses start on the same nanosecond twice.
+pub fn random_seed(time: &Time) -> u32 {
+    let nanos = time.time_since_startup().as_nanos();
+    (nanos ^ nanos >> 32) as u32
+}
+
+fn setup(
+    mut commands: Commands,
+    image_assets: Res<ImageAssets>,
+    mut texture_atlases: ResMut<Assets<TextureAtlas>>,
+) {
+    let atlas = TextureAtlas::from_grid(
+        image_assets.sprite_sheet.clone(),
+        Vec2::splat(GRID_SIZE),
+        8,
+        12,
+    );
+
+    commands.insert_resource(FloorTextures {
+        atlas: texture_atlases.add(atlas),
+    });
+}
+
+/// Runs started from the menu play `levels.ldtk` unless they ask for an endless run again.
+fn end_endless_run(mut commands: Commands) {
+    commands.remove_resource::<EndlessRun>();
+}
+
+fn start_endless_argument(
+    mut commands: Commands,
+    start_endless_run_argument: Option<Res<StartEndlessRun>>,
+    mut game_state: ResMut<State<GameState>>,
+) {
+    let seed = match start_endless_run_argument {
+        Some(start_endless_run_argument) => start_endless_run_argument.0,
+        None => return,
+    };
+
+    commands.remove_resource::<StartEndlessRun>();
+    start_endless_run(&mut commands, seed);
+    game_state.set(GameState::InGame).unwrap();
+}
+
+/// Generates the floor for the current depth whenever it changes or the level is restarted.
+fn spawn_floor(
+    mut commands: Commands,
+    endless_run: Option<Res<EndlessRun>>,
+    level_state: Res<LevelState>,
+    floor_textures: Res<FloorTextures>,
+    mut restart_level_event: EventReader<RestartLevel>,
+    floor_query: Query<(Entity, &EndlessFloor)>,
+) {
+    let endless_run = match endless_run {
+        Some(endless_run) => endless_run,
+        None => return,
+    };
+
+    let depth = level_state.current_level;
+    let restart = restart_level_event.iter().count() > 0;
+    if !restart && floor_query.iter().any(|(_, floor)| floor.depth == depth) {
+        return;
+    }
+
+    for (entity, _) in floor_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    let floor = generate_floor(endless_run.seed, depth);
+
+    commands
+        .spawn_bundle(TransformBundle::identity())
+        .insert(EndlessFloor { depth })
+        .insert(floor_bounds(&floor))
+        .with_children(|parent| {
+            for (cell, index) in floor.wall_tiles.iter() {
+                parent.spawn_bundle(sprite_sheet_bundle(&floor_textures, *index, *cell, WALL_Z));
+            }
+
+            for (min, max) in floor.wall_rects.iter() {
+                parent.spawn_bundle(WallColliderBundle::new(*min, *max));
+            }
+
+            for (cell, entity) in floor.entities.iter() {
+                spawn_entity(parent, &floor_textures, *cell, entity);
+            }
+        });
+}
+
+fn spawn_entity(
+    parent: &mut ChildBuilder,
+    floor_textures: &FloorTextures,
+    cell: IVec2,
+    entity: &FloorEntity,
+) {
+    let pickup = |effect: PickupEffect, index: usize| PickupBundle {
+        pickup: Pickup { effect },
+        grid_position: GridPosition(cell),
+        sprite_sheet_bundle: sprite_sheet_bundle(floor_textures, index, cell, ENTITY_Z),
+        collider_bundle: ColliderBundle::pickup(),
+    };
+
+    match entity {
+        FloorEntity::Player => {
+            parent.spawn_bundle(PlayerBundle {
+                sprite_bundle: sprite_sheet_bundle(floor_textures, PLAYER_SPRITE, cell, ENTITY_Z),
+                collider_bundle: ColliderBundle::player(),
+                ..Default::default()
+            });
+        }
+        FloorEntity::Stairs => {
+            parent.spawn_bundle(pickup(PickupEffect::Stairs, STAIRS_SPRITE));
+        }
+        FloorEntity::Coin => {
+            parent.spawn_bundle(pickup(PickupEffect::Coin, COIN_SPRITE));
+        }
+        FloorEntity::Potion(kind) => {
+            parent.spawn_bundle(pickup(PickupEffect::Item(*kind), kind.sprite_index()));
+        }
+        FloorEntity::Enemy(kind) => {
+            // `setup_enemy` picks the sprite for the kind
+            parent.spawn_bundle(EnemyBundle {
+                archetype: EnemyArchetype {
+                    kind: kind.to_string(),
+                    ..Default::default()
+                },
+                sprite_bundle: sprite_sheet_bundle(floor_textures, 0, cell, ENTITY_Z),
+                collider_bundle: ColliderBundle::enemy(),
+                ..Default::default()
+            });
+        }
+    }
+}
+
+/// The walls surround everything else on the floor.
+fn floor_bounds(floor: &Floor) -> CameraBounds {
+    let min = floor
+        .wall_tiles
+        .iter()
+        .map(|(cell, _)| *cell)
+        .reduce(IVec2::min);
+    let max = floor
+        .wall_tiles
+        .iter()
+        .map(|(cell, _)| *cell)
+        .reduce(IVec2::max);
+
+    CameraBounds {
+        min: min.unwrap_or_default().as_vec2() * GRID_SIZE,
+        max: (max.unwrap_or_default() + IVec2::ONE).as_vec2() * GRID_SIZE,
+    }
+}
+
+fn cell_center(cell: IVec2) -> Vec2 {
+    cell.as_vec2() * GRID_SIZE + Vec2::splat(GRID_SIZE / 2.)
+}
+
+fn sprite_sheet_bundle(
+    floor_textures: &FloorTextures,
+    index: usize,
+    cell: IVec2,
+    z: f32,
+) -> SpriteSheetBundle {
+    SpriteSheetBundle {
+        sprite: TextureAtlasSprite::new(index),
+        texture_atlas: floor_textures.atlas.clone(),
+        transform: Transform::from_translation(cell_center(cell).extend(z)),
+        ..Default::default()
+    }
+}
+
+fn cleanup(mut commands: Commands, query: Query<Entity, With<EndlessFloor>>) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
@@ -1,5 +1,8 @@
+use crate::game::components::{Damage, GridPosition, LockedStairsUnderfoot};
 use crate::game::events::*;
-use crate::game::game::GameWorldState;
+use crate::game::game::{GameWorldState, HEALTH_PER_HEART};
+use crate::game::pickup::{Pickup, PickupEffect};
+use crate::game::tutorial::{TutorialProgress, TutorialTrigger};
 use crate::levels::IncrementLevel;
 use bevy::prelude::*;
 use heron::CollisionEvent;
@@ -11,6 +14,8 @@ pub fn player_attack_collision(
     collision_events: EventReader<CollisionEvent>,
     mut event_writer: EventWriter<PlayerDamaged>,
     game_world_state: Res<GameWorldState>,
+    transform_query: Query<&GlobalTransform>,
+    damage_query: Query<&Damage>,
 ) {
     if game_world_state.is_borking {
         return;
@@ -21,58 +26,102 @@ pub fn player_attack_collision(
         is_enemy_attack,
         is_player,
         move |enemy_attack_entity, _| {
-            event_writer.send(PlayerDamaged::default());
+            event_writer.send(PlayerDamaged {
+                from: transform_query
+                    .get(enemy_attack_entity)
+                    .ok()
+                    .map(|transform| transform.translation),
+                damage: damage_query
+                    .get(enemy_attack_entity)
+                    .map(|damage| damage.0)
+                    .unwrap_or(HEALTH_PER_HEART),
+            });
             commands.entity(enemy_attack_entity).despawn();
         },
     );
 }
 
-pub fn player_item_collision(
+/// Despawns whatever pickup the player touched and sends the event for its effect. Stairs are
+/// left alone until the level's tutorial is done.
+#[allow(clippy::too_many_arguments)]
+pub fn player_pickup_collision(
     mut commands: Commands,
     collision_events: EventReader<CollisionEvent>,
-    mut event_writer: EventWriter<PickupItem>,
+    mut item_event_writer: EventWriter<PickupItem>,
+    mut coin_event_writer: EventWriter<PickupCoin>,
+    mut increment_level_event_writer: EventWriter<IncrementLevel>,
+    mut stairs_locked_event_writer: EventWriter<StairsLocked>,
+    tutorial_progress: Res<TutorialProgress>,
+    pickup_query: Query<(&Pickup, Option<&GridPosition>)>,
+    tutorial_query: Query<&TutorialTrigger>,
 ) {
     filter_events(
         collision_events,
-        is_item,
+        is_pickup,
         is_player,
-        move |item_entity, _| {
-            commands.entity(item_entity).despawn_recursive();
-            event_writer.send(PickupItem::default());
+        move |pickup_entity, _| {
+            let (pickup, grid_position) = match pickup_query.get(pickup_entity) {
+                Ok(result) => result,
+                Err(_) => return,
+            };
+
+            if pickup.effect == PickupEffect::Stairs
+                && !tutorial_progress.is_complete(tutorial_query.iter())
+            {
+                stairs_locked_event_writer.send(StairsLocked);
+                commands.entity(pickup_entity).insert(LockedStairsUnderfoot);
+                return;
+            }
+
+            commands.entity(pickup_entity).despawn_recursive();
+            match pickup.effect {
+                PickupEffect::Item(kind) => item_event_writer.send(PickupItem { kind }),
+                PickupEffect::Coin => coin_event_writer.send(PickupCoin {
+                    grid: grid_position.map(|grid| grid.0),
+                }),
+                PickupEffect::Stairs => {
+                    increment_level_event_writer.send(IncrementLevel::default())
+                }
+            }
         },
     );
 }
 
-pub fn player_coin_collision(
+/// Only the player collides with pickups, so any contact with locked stairs ending means they
+/// stepped off.
+pub fn player_left_locked_stairs(
     mut commands: Commands,
-    collision_events: EventReader<CollisionEvent>,
-    mut event_writer: EventWriter<PickupCoin>,
+    mut collision_events: EventReader<CollisionEvent>,
+    stairs_query: Query<(), With<LockedStairsUnderfoot>>,
 ) {
-    filter_events(
-        collision_events,
-        is_coin,
-        is_player,
-        move |coin_entity, _| {
-            commands.entity(coin_entity).despawn_recursive();
-            event_writer.send(PickupCoin::default());
-        },
-    );
+    for event in collision_events.iter().filter(|event| event.is_stopped()) {
+        let (entity_1, entity_2) = event.rigid_body_entities();
+
+        for entity in [entity_1, entity_2] {
+            if stairs_query.get(entity).is_ok() {
+                commands.entity(entity).remove::<LockedStairsUnderfoot>();
+            }
+        }
+    }
 }
 
-pub fn player_stairs_collision(
+/// Takes the stairs as soon as the tutorial is done, when the player is already standing on them.
+pub fn take_unlocked_stairs(
     mut commands: Commands,
-    collision_events: EventReader<CollisionEvent>,
-    mut event_writer: EventWriter<IncrementLevel>,
+    mut increment_level_event_writer: EventWriter<IncrementLevel>,
+    tutorial_progress: Res<TutorialProgress>,
+    stairs_query: Query<Entity, With<LockedStairsUnderfoot>>,
+    tutorial_query: Query<&TutorialTrigger>,
 ) {
-    filter_events(
-        collision_events,
-        is_stairs,
-        is_player,
-        move |stairs_entity, _| {
-            commands.entity(stairs_entity).despawn_recursive();
-            event_writer.send(IncrementLevel::default());
-        },
-    );
+    let stairs = match stairs_query.iter().next() {
+        Some(stairs) => stairs,
+        None => return,
+    };
+
+    if tutorial_progress.is_complete(tutorial_query.iter()) {
+        commands.entity(stairs).despawn_recursive();
+        increment_level_event_writer.send(IncrementLevel::default());
+    }
 }
 
 pub fn player_attack_enemy_attack_collision(
@@ -89,6 +138,23 @@ pub fn player_attack_enemy_attack_collision(
     );
 }
 
+pub fn player_attack_enemy_collision(
+    collision_events: EventReader<CollisionEvent>,
+    mut event_writer: EventWriter<EnemyDamaged>,
+) {
+    filter_events(
+        collision_events,
+        is_enemy,
+        is_player_attack,
+        move |enemy_entity, player_attack_entity| {
+            event_writer.send(EnemyDamaged {
+                enemy: enemy_entity,
+                attacker: player_attack_entity,
+            });
+        },
+    );
+}
+
 pub fn filter_events<F>(
     mut collision_events: EventReader<CollisionEvent>,
     expected_filter: CollisionLayerFilter,
@@ -10,24 +10,20 @@ pub fn is_player(layers: CollisionLayers) -> bool {
         && !layers.contains_group(GameCollisionLayers::EnemyAttack)
 }
 
-pub fn is_enemy_attack(layers: CollisionLayers) -> bool {
-    !layers.contains_group(GameCollisionLayers::Player)
-        && layers.contains_group(GameCollisionLayers::EnemyAttack)
-}
-
-pub fn is_item(layers: CollisionLayers) -> bool {
-    !layers.contains_group(GameCollisionLayers::Player)
-        && layers.contains_group(GameCollisionLayers::Item)
+pub fn is_enemy(layers: CollisionLayers) -> bool {
+    layers.contains_group(GameCollisionLayers::Enemy)
+        && !layers.contains_group(GameCollisionLayers::EnemyAttack)
+        && !layers.contains_group(GameCollisionLayers::PlayerAttack)
 }
 
-pub fn is_coin(layers: CollisionLayers) -> bool {
+pub fn is_enemy_attack(layers: CollisionLayers) -> bool {
     !layers.contains_group(GameCollisionLayers::Player)
-        && layers.contains_group(GameCollisionLayers::Coin)
+        && layers.contains_group(GameCollisionLayers::EnemyAttack)
 }
 
-pub fn is_stairs(layers: CollisionLayers) -> bool {
+pub fn is_pickup(layers: CollisionLayers) -> bool {
     !layers.contains_group(GameCollisionLayers::Player)
-        && layers.contains_group(GameCollisionLayers::Stairs)
+        && layers.contains_group(GameCollisionLayers::Pickup)
 }
 
 pub fn is_player_attack(layers: CollisionLayers) -> bool {
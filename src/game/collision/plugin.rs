@@ -10,10 +10,11 @@ impl Plugin for CollisionPlugin {
         app.add_system_set(
             SystemSet::on_update(GameState::InGame)
                 .with_system(player_attack_collision)
-                .with_system(player_item_collision)
-                .with_system(player_coin_collision)
-                .with_system(player_stairs_collision)
-                .with_system(player_attack_enemy_attack_collision),
+                .with_system(player_pickup_collision)
+                .with_system(player_left_locked_stairs)
+                .with_system(take_unlocked_stairs.after("complete_tutorial_steps"))
+                .with_system(player_attack_enemy_attack_collision)
+                .with_system(player_attack_enemy_collision),
         );
     }
 }
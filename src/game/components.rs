@@ -7,17 +7,16 @@ pub struct Player;
 #[derive(Component, Default, Clone)]
 pub struct Enemy;
 
-#[derive(Component, Default, Clone)]
-pub struct Item;
-
 #[derive(Component, Clone, Debug, Default)]
-pub struct Coin;
+pub struct Wall;
 
+/// Where an entity was placed in its LDtk level, in grid cells.
 #[derive(Component, Clone, Debug, Default)]
-pub struct Wall;
+pub struct GridPosition(pub IVec2);
 
+/// On stairs the player walked onto while the tutorial kept them locked, until they step off.
 #[derive(Component, Clone, Debug, Default)]
-pub struct Stairs;
+pub struct LockedStairsUnderfoot;
 
 #[derive(Component, Default, Clone)]
 pub struct Bork;
@@ -28,6 +27,23 @@ pub struct Speed(pub f32);
 #[derive(Component)]
 pub struct TimeToLive(pub Timer);
 
+/// Health an enemy attack takes from the player when it hits, in half hearts.
+#[derive(Component, Clone, Copy, Debug)]
+pub struct Damage(pub u32);
+
+/// Pushes an entity away from a hit. Movement and AI leave its velocity alone until the timer
+/// finishes.
+#[derive(Component, Default, Clone, Debug)]
+pub struct Knockback(pub Timer);
+
+/// The player drank a speed potion and moves faster until the timer finishes.
+#[derive(Component, Default, Clone, Debug)]
+pub struct SpeedBoost(pub Timer);
+
+/// The player was hit recently and can't be hurt again until the timer finishes.
+#[derive(Component, Default, Clone, Debug)]
+pub struct Invulnerable(pub Timer);
+
 #[derive(PhysicsLayer)]
 pub enum GameCollisionLayers {
     World,
@@ -35,7 +51,5 @@ pub enum GameCollisionLayers {
     PlayerAttack,
     Enemy,
     EnemyAttack,
-    Item,
-    Coin,
-    Stairs,
+    Pickup,
 }
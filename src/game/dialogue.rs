@@ -0,0 +1,518 @@
+use bevy::prelude::*;
+
+use crate::{
+    bitmap_text::{cut_tiles, BitmapText, GLYPH_SIZE, TEXT_COLOR},
+    camera::WINDOW_SCALE,
+    input::Action,
+    types::{GameState, ImageAssets},
+};
+
+use super::components::Player;
+use super::time::GameTime;
+
+/// LDtk entities that are spawned as a `DialogueBundle`. Their multi-line `text` field is shown
+/// when the player walks up to them and presses Confirm.
+pub const DIALOGUE_ENTITIES: [&str; 2] = ["Sign", "Npc"];
+
+/// Characters that fit on a line of the dialogue box.
+pub const DIALOGUE_COLUMNS: usize = 30;
+
+/// Lines shown at once, longer pages continue on the next one.
+pub const DIALOGUE_LINES: usize = 3;
+
+/// How close the player has to be to read a sign or talk to someone.
+const TALK_DISTANCE: f32 = 14.;
+
+const CHARACTERS_PER_SECOND: f32 = 40.;
+
+/// The prompt floats this far above whoever can be talked to.
+const PROMPT_OFFSET: f32 = 10.;
+
+const PROMPT_Z: f32 = 50.;
+
+/// Tiles of `dialogue.png` making up the box, corners and edges clockwise from the top left.
+const FRAME_TILES: [usize; 8] = [77, 78, 79, 95, 111, 110, 109, 93];
+
+/// Something the player can read or talk to. Its text is split into pages of at most
+/// `DIALOGUE_LINES` lines when the level is loaded.
+#[derive(Component, Clone, Debug, Default)]
+pub struct Dialogue {
+    pub pages: Vec<Vec<String>>,
+    /// Shown above it while the player is close enough
+    pub prompt: &'static str,
+}
+
+/// The dialogue being read, if any. Player movement stops while it is open.
+#[derive(Default, Clone, Debug)]
+pub struct DialogueState {
+    open: Option<OpenDialogue>,
+}
+
+impl DialogueState {
+    pub fn is_open(&self) -> bool {
+        self.open.is_some()
+    }
+}
+
+#[derive(Clone, Debug)]
+struct OpenDialogue {
+    /// The entity with the `Dialogue`
+    source: Entity,
+    pages: Vec<Vec<String>>,
+    page: usize,
+    /// Characters of the page typed out so far
+    revealed: f32,
+}
+
+impl OpenDialogue {
+    fn page_length(&self) -> usize {
+        self.pages[self.page]
+            .iter()
+            .map(|line| line.chars().count())
+            .sum()
+    }
+
+    fn is_page_done(&self) -> bool {
+        self.revealed as usize >= self.page_length()
+    }
+}
+
+struct DialogueFrame {
+    /// Indexed like `FRAME_TILES`
+    tiles: Vec<Handle<Image>>,
+}
+
+#[derive(Component, Default, Clone, Debug)]
+pub struct DialogueBox;
+
+#[derive(Component, Clone, Debug)]
+pub struct DialogueLine(usize);
+
+/// Floats above the entity it belongs to.
+#[derive(Component, Clone, Debug)]
+pub struct DialoguePrompt(Entity);
+
+pub struct DialoguePlugin;
+
+impl Plugin for DialoguePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<DialogueState>()
+            .add_system_set(SystemSet::on_exit(GameState::Loading).with_system(setup))
+            .add_system_set(
+                SystemSet::on_update(GameState::InGame)
+                    .with_system(close_despawned.before("interact"))
+                    .with_system(interact.label("interact"))
+                    .with_system(show_prompt.after("interact"))
+                    .with_system(show_dialogue_box.after("interact"))
+                    .with_system(type_text.after("interact")),
+            )
+            .add_system_set(SystemSet::on_exit(GameState::InGame).with_system(cleanup));
+    }
+}
+
+/// Splits the text into pages at blank lines and wraps each line to fit the box.
+pub fn paginate(text: &str) -> Vec<Vec<String>> {
+    let mut pages = Vec::new();
+
+    for paragraph in text.split("\n\n") {
+        let lines: Vec<String> = paragraph.lines().flat_map(wrap).collect();
+        for page in lines.chunks(DIALOGUE_LINES) {
+            pages.push(page.to_vec());
+        }
+    }
+
+    pages.retain(|page| page.iter().any(|line| !line.is_empty()));
+    pages
+}
+
+/// Breaks a line between words, and words too long for a line wherever they reach the edge.
+fn wrap(line: &str) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut current = String::new();
+
+    for word in line.split_whitespace() {
+        let mut word: Vec<char> = word.chars().collect();
+
+        let needed = if current.is_empty() {
+            word.len()
+        } else {
+            current.chars().count() + 1 + word.len()
+        };
+        if needed > DIALOGUE_COLUMNS && !current.is_empty() {
+            lines.push(std::mem::take(&mut current));
+        }
+
+        while word.len() > DIALOGUE_COLUMNS {
+            lines.push(word.drain(..DIALOGUE_COLUMNS).collect());
+        }
+
+        if !current.is_empty() {
+            current.push(' ');
+        }
+        current.extend(word);
+    }
+
+    lines.push(current);
+    lines
+}
+
+fn setup(
+    mut commands: Commands,
+    image_assets: Res<ImageAssets>,
+    mut images: ResMut<Assets<Image>>,
+) {
+    let sheet = match images.get(&image_assets.dialogue) {
+        Some(sheet) => sheet.clone(),
+        None => return,
+    };
+
+    let tiles = cut_tiles(&sheet, &mut images);
+    commands.insert_resource(DialogueFrame {
+        tiles: FRAME_TILES
+            .iter()
+            .map(|index| tiles[*index].clone())
+            .collect(),
+    });
+}
+
+/// The closest thing the player is near enough to talk to.
+fn nearest_dialogue<'a>(
+    player: Vec2,
+    query: &'a Query<(Entity, &Dialogue, &GlobalTransform)>,
+) -> Option<(Entity, &'a Dialogue, Vec2)> {
+    query
+        .iter()
+        .map(|(entity, dialogue, transform)| (entity, dialogue, transform.translation.truncate()))
+        .filter(|(_, _, position)| position.distance(player) <= TALK_DISTANCE)
+        .min_by(|(_, _, a), (_, _, b)| {
+            a.distance(player)
+                .partial_cmp(&b.distance(player))
+                .unwrap_or(std::cmp::Ordering::Equal)
+        })
+}
+
+/// Confirm opens the dialogue in reach, finishes typing the page and then turns to the next one.
+fn interact(
+    mut actions: ResMut<Input<Action>>,
+    mut dialogue_state: ResMut<DialogueState>,
+    player_query: Query<&GlobalTransform, With<Player>>,
+    dialogue_query: Query<(Entity, &Dialogue, &GlobalTransform)>,
+) {
+    if !actions.just_pressed(Action::Confirm) {
+        return;
+    }
+
+    if let Some(open) = dialogue_state.open.as_mut() {
+        if !open.is_page_done() {
+            open.revealed = open.page_length() as f32;
+        } else if open.page + 1 < open.pages.len() {
+            open.page += 1;
+            open.revealed = 0.;
+        } else {
+            dialogue_state.open = None;
+        }
+    } else {
+        let player = match player_query.get_single() {
+            Ok(transform) => transform.translation.truncate(),
+            Err(_) => return,
+        };
+
+        match nearest_dialogue(player, &dialogue_query) {
+            Some((source, dialogue, _)) if !dialogue.pages.is_empty() => {
+                dialogue_state.open = Some(OpenDialogue {
+                    source,
+                    pages: dialogue.pages.clone(),
+                    page: 0,
+                    revealed: 0.,
+                });
+            }
+            _ => return,
+        }
+    }
+
+    // Confirm and Bork share a button, talking shouldn't bork as well
+    actions.clear_just_pressed(Action::Bork);
+}
+
+/// Restarting or leaving the level despawns whatever was being read. Its box would otherwise stay
+/// open over the new level and keep the player frozen.
+fn close_despawned(mut dialogue_state: ResMut<DialogueState>, query: Query<(), With<Dialogue>>) {
+    let despawned = match &dialogue_state.open {
+        Some(open) => !query.contains(open.source),
+        None => false,
+    };
+
+    if despawned {
+        dialogue_state.open = None;
+    }
+}
+
+fn show_prompt(
+    mut commands: Commands,
+    dialogue_state: Res<DialogueState>,
+    player_query: Query<&GlobalTransform, With<Player>>,
+    dialogue_query: Query<(Entity, &Dialogue, &GlobalTransform)>,
+    prompt_query: Query<(Entity, &DialoguePrompt)>,
+) {
+    let nearest = match player_query.get_single() {
+        Ok(transform) if !dialogue_state.is_open() => {
+            nearest_dialogue(transform.translation.truncate(), &dialogue_query)
+        }
+        _ => None,
+    };
+
+    let mut shown = false;
+    for (prompt_entity, prompt) in prompt_query.iter() {
+        if nearest.map(|(entity, _, _)| entity) == Some(prompt.0) {
+            shown = true;
+        } else {
+            commands.entity(prompt_entity).despawn_recursive();
+        }
+    }
+
+    if let Some((entity, dialogue, position)) = nearest {
+        if !shown {
+            let position = position + Vec2::new(0., PROMPT_OFFSET);
+            commands
+                .spawn_bundle(TransformBundle::from_transform(
+                    Transform::from_translation(position.round().extend(PROMPT_Z)),
+                ))
+                .insert(BitmapText::new(dialogue.prompt))
+                .insert(DialoguePrompt(entity));
+        }
+    }
+}
+
+fn show_dialogue_box(
+    mut commands: Commands,
+    dialogue_state: Res<DialogueState>,
+    frame: Option<Res<DialogueFrame>>,
+    query: Query<Entity, With<DialogueBox>>,
+) {
+    if !dialogue_state.is_open() {
+        for entity in query.iter() {
+            commands.entity(entity).despawn_recursive();
+        }
+        return;
+    }
+
+    let frame = match frame {
+        Some(frame) => frame,
+        None => return,
+    };
+    if !query.is_empty() {
+        return;
+    }
+
+    let tile = GLYPH_SIZE * WINDOW_SCALE;
+    let inner = Size::new(
+        Val::Px(DIALOGUE_COLUMNS as f32 * tile),
+        Val::Px(DIALOGUE_LINES as f32 * tile),
+    );
+    let frame_tile = |parent: &mut ChildBuilder, index: usize, size: Size<Val>| {
+        parent.spawn_bundle(ImageBundle {
+            style: Style {
+                size,
+                ..Default::default()
+            },
+            image: frame.tiles[index].clone().into(),
+            color: TEXT_COLOR.into(),
+            ..Default::default()
+        });
+    };
+    let corner = Size::new(Val::Px(tile), Val::Px(tile));
+    let horizontal_edge = Size::new(inner.width, Val::Px(tile));
+    let vertical_edge = Size::new(Val::Px(tile), inner.height);
+
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                size: Size::new(Val::Percent(100.), Val::Auto),
+                position_type: PositionType::Absolute,
+                position: Rect {
+                    bottom: Val::Px(tile),
+                    ..Default::default()
+                },
+                justify_content: JustifyContent::Center,
+                ..Default::default()
+            },
+            color: Color::NONE.into(),
+            ..Default::default()
+        })
+        .insert(DialogueBox)
+        .with_children(|parent| {
+            parent
+                .spawn_bundle(NodeBundle {
+                    style: Style {
+                        flex_direction: FlexDirection::ColumnReverse,
+                        ..Default::default()
+                    },
+                    color: Color::rgb(0.098, 0.078, 0.169).into(),
+                    ..Default::default()
+                })
+                .with_children(|parent| {
+                    parent
+                        .spawn_bundle(NodeBundle {
+                            color: Color::NONE.into(),
+                            ..Default::default()
+                        })
+                        .with_children(|parent| {
+                            frame_tile(parent, 0, corner);
+                            frame_tile(parent, 1, horizontal_edge);
+                            frame_tile(parent, 2, corner);
+                        });
+
+                    parent
+                        .spawn_bundle(NodeBundle {
+                            color: Color::NONE.into(),
+                            ..Default::default()
+                        })
+                        .with_children(|parent| {
+                            frame_tile(parent, 7, vertical_edge);
+                            parent
+                                .spawn_bundle(NodeBundle {
+                                    style: Style {
+                                        size: inner,
+                                        flex_direction: FlexDirection::ColumnReverse,
+                                        ..Default::default()
+                                    },
+                                    color: Color::NONE.into(),
+                                    ..Default::default()
+                                })
+                                .with_children(|parent| {
+                                    for index in 0..DIALOGUE_LINES {
+                                        parent
+                                            .spawn_bundle(NodeBundle {
+                                                color: Color::NONE.into(),
+                                                ..Default::default()
+                                            })
+                                            .insert(BitmapText::new(""))
+                                            .insert(DialogueLine(index));
+                                    }
+                                });
+                            frame_tile(parent, 3, vertical_edge);
+                        });
+
+                    parent
+                        .spawn_bundle(NodeBundle {
+                            color: Color::NONE.into(),
+                            ..Default::default()
+                        })
+                        .with_children(|parent| {
+                            frame_tile(parent, 6, corner);
+                            frame_tile(parent, 5, horizontal_edge);
+                            frame_tile(parent, 4, corner);
+                        });
+                });
+        });
+}
+
+/// Types the page out a few characters at a time.
+fn type_text(
+    game_time: Res<GameTime>,
+    mut dialogue_state: ResMut<DialogueState>,
+    mut query: Query<(&mut BitmapText, &DialogueLine)>,
+) {
+    let open = match dialogue_state.open.as_mut() {
+        Some(open) => open,
+        None => return,
+    };
+
+    if !open.is_page_done() {
+        open.revealed += CHARACTERS_PER_SECOND * game_time.delta().as_secs_f32();
+    }
+
+    let lines = &open.pages[open.page];
+    for (mut text, line) in query.iter_mut() {
+        // Lines before this one use up their share of the typed characters first
+        let before: usize = lines
+            .iter()
+            .take(line.0)
+            .map(|line| line.chars().count())
+            .sum();
+        let shown = (open.revealed as usize).saturating_sub(before);
+
+        let value: String = lines
+            .get(line.0)
+            .map(|line| line.chars().take(shown).collect())
+            .unwrap_or_default();
+        if text.value != value {
+            text.value = value;
+        }
+    }
+}
+
+fn cleanup(
+    mut commands: Commands,
+    mut dialogue_state: ResMut<DialogueState>,
+    query: Query<Entity, Or<(With<DialogueBox>, With<DialoguePrompt>)>>,
+) {
+    dialogue_state.open = None;
+
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{paginate, DIALOGUE_COLUMNS, DIALOGUE_LINES};
+
+    fn page(lines: &[&str]) -> Vec<String> {
+        lines.iter().map(|line| line.to_string()).collect()
+    }
+
+    #[test]
+    fn lines_wrap_between_words() {
+        let pages = paginate("the quick brown fox jumps over the lazy dog");
+
+        assert_eq!(
+            pages,
+            vec![page(&["the quick brown fox jumps over", "the lazy dog"])]
+        );
+        assert!(pages[0].iter().all(|line| line.len() <= DIALOGUE_COLUMNS));
+    }
+
+    #[test]
+    fn words_longer_than_a_line_are_split() {
+        let long_word = "x".repeat(DIALOGUE_COLUMNS + 5);
+
+        assert_eq!(
+            paginate(&format!("a {} b", long_word)),
+            vec![page(&[
+                "a",
+                &long_word[..DIALOGUE_COLUMNS],
+                &format!("{} b", &long_word[DIALOGUE_COLUMNS..]),
+            ])]
+        );
+    }
+
+    #[test]
+    fn blank_lines_start_a_new_page() {
+        assert_eq!(
+            paginate("first\nstill first\n\nsecond\n\n\n\nthird"),
+            vec![
+                page(&["first", "still first"]),
+                page(&["second"]),
+                page(&["third"]),
+            ]
+        );
+    }
+
+    #[test]
+    fn long_paragraphs_continue_on_the_next_page() {
+        let pages = paginate("one\ntwo\nthree\nfour\nfive");
+
+        assert_eq!(
+            pages,
+            vec![page(&["one", "two", "three"]), page(&["four", "five"])]
+        );
+        assert!(pages.iter().all(|page| page.len() <= DIALOGUE_LINES));
+    }
+
+    #[test]
+    fn empty_text_has_no_pages() {
+        assert!(paginate("").is_empty());
+        assert!(paginate("\n\n").is_empty());
+    }
+}
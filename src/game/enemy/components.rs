@@ -1,5 +1,7 @@
 use bevy::prelude::*;
 
+use super::kinds::{ProjectilePattern, DEFAULT_ENEMY_KIND};
+
 #[derive(Component, Default, Clone, Debug)]
 pub struct Aggroable {
     pub distance: f32,
@@ -15,4 +17,40 @@ pub struct AttackPlayer;
 pub struct Attacking {
     pub timer: Timer,
     pub is_attacking: bool,
+    pub projectile: ProjectilePattern,
+}
+
+/// Which `EnemyKind` an enemy is spawned as, along with any values the level overrides.
+#[derive(Component, Clone, Debug)]
+pub struct EnemyArchetype {
+    pub kind: String,
+    pub health: Option<u32>,
+    pub coins: Option<u32>,
+}
+
+impl Default for EnemyArchetype {
+    fn default() -> Self {
+        Self {
+            kind: DEFAULT_ENEMY_KIND.to_string(),
+            health: None,
+            coins: None,
+        }
+    }
+}
+
+pub const ENEMY_DEFAULT_HEALTH: u32 = 3;
+
+#[derive(Component, Clone, Debug)]
+pub struct Health(pub u32);
+
+impl Default for Health {
+    fn default() -> Self {
+        Health(ENEMY_DEFAULT_HEALTH)
+    }
+}
+
+/// Coins dropped where the enemy dies.
+#[derive(Component, Default, Clone, Debug)]
+pub struct Loot {
+    pub coins: u32,
 }
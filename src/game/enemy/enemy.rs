@@ -3,41 +3,87 @@ use big_brain::prelude::FirstToScore;
 use big_brain::prelude::Thinker;
 use heron::prelude::*;
 
+use crate::game::events::{EnemyDamaged, EnemyKilled};
 use crate::game::level::components::ColliderBundle;
+use crate::game::level::components::DroppedCoinBundle;
 use crate::game::level::components::ProjectileBundle;
-use crate::types::ImageAssets;
+use crate::game::pickup::{Pickup, PickupEffect};
+use crate::game::time::GameTime;
+use crate::types::{EnemyAssets, ImageAssets};
 use crate::{game::components::GameCollisionLayers, types::GameState};
 
 use super::{
-    super::components::{Enemy, Player, TimeToLive},
-    components::{Aggroable, Aggroed, AttackPlayer, Attacking},
+    super::components::{Damage, Enemy, Knockback, Player, Speed, TimeToLive},
+    components::{Aggroable, Aggroed, AttackPlayer, Attacking, EnemyArchetype, Health, Loot},
+    kinds::{EnemyKind, EnemyKinds, EnemyKindsLoader},
     shaman_ai::ShamanAi,
 };
 
+const KNOCKBACK_SPEED: f32 = 120.;
+const KNOCKBACK_SECONDS: f32 = 0.2;
+
 pub struct EnemyPlugin;
 
 impl Plugin for EnemyPlugin {
     fn build(&self, app: &mut App) {
-        app.add_plugin(ShamanAi).add_system_set(
-            SystemSet::on_update(GameState::InGame)
-                .with_system(setup_enemy)
-                .with_system(on_shoot),
-        );
+        app.add_asset::<EnemyKinds>()
+            .init_asset_loader::<EnemyKindsLoader>()
+            .add_plugin(ShamanAi)
+            .add_system_set(
+                SystemSet::on_update(GameState::InGame)
+                    .with_system(setup_enemy)
+                    .with_system(on_shoot)
+                    .with_system(enemy_damaged)
+                    .with_system(enemy_killed),
+            );
     }
 }
 
 fn setup_enemy(
     mut commands: Commands,
-    mut query: Query<(Entity, &mut TextureAtlasSprite, &mut Aggroable), Added<Enemy>>,
+    enemy_assets: Res<EnemyAssets>,
+    enemy_kinds: Res<Assets<EnemyKinds>>,
+    mut query: Query<
+        (
+            Entity,
+            &EnemyArchetype,
+            &mut TextureAtlasSprite,
+            &mut Aggroable,
+            &mut Speed,
+            &mut Health,
+            &mut Loot,
+        ),
+        Added<Enemy>,
+    >,
 ) {
-    for (entity, mut sprite, mut aggroable) in query.iter_mut() {
-        sprite.index = 89;
-        aggroable.distance = 100.;
+    let enemy_kinds = enemy_kinds.get(&enemy_assets.kinds);
+
+    for (entity, archetype, mut sprite, mut aggroable, mut speed, mut health, mut loot) in
+        query.iter_mut()
+    {
+        let kind = match enemy_kinds.and_then(|kinds| kinds.0.get(&archetype.kind)) {
+            Some(kind) => kind.clone(),
+            None => {
+                warn!(
+                    "Unknown enemy kind \"{}\", falling back to the default",
+                    archetype.kind
+                );
+                EnemyKind::default()
+            }
+        };
+
+        sprite.index = kind.sprite_index;
+        aggroable.distance = kind.aggro_distance;
+        speed.0 = kind.speed;
+        health.0 = archetype.health.unwrap_or(kind.health);
+        loot.coins = archetype.coins.unwrap_or(kind.coins);
+
         commands
             .entity(entity)
             .insert(Attacking {
-                timer: Timer::from_seconds(1., true),
+                timer: Timer::from_seconds(kind.fire_interval, true),
                 is_attacking: false,
+                projectile: kind.projectile,
             })
             .insert(
                 Thinker::build()
@@ -49,7 +95,7 @@ fn setup_enemy(
 
 fn on_shoot(
     mut commands: Commands,
-    time: Res<Time>,
+    game_time: Res<GameTime>,
     image_assets: Res<ImageAssets>,
     player_query: Query<&Transform, With<Player>>,
     mut query: Query<(&Transform, &mut Attacking), With<Enemy>>,
@@ -58,51 +104,144 @@ fn on_shoot(
     if let Ok(player_transform) = player_query.get_single() {
         for (transform, mut attacking) in query.iter_mut() {
             // Only shoot when the cooldown is over
-            if !attacking.is_attacking || !attacking.timer.tick(time.delta()).just_finished() {
+            if !attacking.is_attacking || !attacking.timer.tick(game_time.delta()).just_finished() {
                 continue;
             }
 
-            let direction =
-                (player_transform.translation - transform.translation).normalize_or_zero();
+            let pattern = &attacking.projectile;
+            let aim = (player_transform.translation - transform.translation).normalize_or_zero();
 
-            // Make sure that the projectiles spawn outside of the body so that it doesn't collide
-            let beyond_body_diff = direction * 8.;
-            let mut new_transform = transform.clone();
-            new_transform.translation = transform.translation + beyond_body_diff;
+            for index in 0..pattern.count {
+                // Fan the projectiles out evenly across the spread, centered on the player
+                let angle = if pattern.count > 1 {
+                    pattern.spread * (index as f32 / (pattern.count - 1) as f32 - 0.5)
+                } else {
+                    0.
+                };
+                let direction = Quat::from_rotation_z(angle.to_radians()) * aim;
 
-            commands
-                .spawn_bundle(ProjectileBundle {
-                    sprite_bundle: SpriteBundle {
-                        texture: image_assets.energy_star.clone(),
-                        transform: new_transform,
-                        ..Default::default()
-                    },
+                // Make sure that the projectiles spawn outside of the body so that it doesn't collide
+                let beyond_body_diff = direction * 8.;
+                let mut new_transform = transform.clone();
+                new_transform.translation = transform.translation + beyond_body_diff;
 
-                    collider_bundle: ColliderBundle {
-                        collider: CollisionShape::Cuboid {
-                            half_extends: Vec3::new(4., 4., 0.),
-                            border_radius: None,
+                commands
+                    .spawn_bundle(ProjectileBundle {
+                        sprite_bundle: SpriteBundle {
+                            texture: image_assets.energy_star.clone(),
+                            transform: new_transform,
+                            ..Default::default()
                         },
-                        collision_layers: CollisionLayers::none()
-                            .with_group(GameCollisionLayers::EnemyAttack)
-                            .with_masks(&[
-                                GameCollisionLayers::World,
-                                GameCollisionLayers::Player,
-                                GameCollisionLayers::PlayerAttack,
-                            ]),
-                        rigid_body: RigidBody::Dynamic,
-                        rotation_constraints,
-                        ..Default::default()
-                    },
 
-                    ttl: TimeToLive(Timer::from_seconds(0.5, false)),
-                })
-                .insert(PhysicMaterial {
-                    restitution: 0.7,
-                    density: 1.,
-                    friction: 0.5,
-                })
-                .insert(Velocity::from_linear(direction * 150.));
+                        collider_bundle: ColliderBundle {
+                            collider: CollisionShape::Cuboid {
+                                half_extends: Vec3::new(4., 4., 0.),
+                                border_radius: None,
+                            },
+                            collision_layers: CollisionLayers::none()
+                                .with_group(GameCollisionLayers::EnemyAttack)
+                                .with_masks(&[
+                                    GameCollisionLayers::World,
+                                    GameCollisionLayers::Player,
+                                    GameCollisionLayers::PlayerAttack,
+                                ]),
+                            rigid_body: RigidBody::Dynamic,
+                            rotation_constraints,
+                            ..Default::default()
+                        },
+
+                        ttl: TimeToLive(Timer::from_seconds(pattern.time_to_live, false)),
+                        damage: Damage(pattern.damage),
+                    })
+                    .insert(PhysicMaterial {
+                        restitution: 0.7,
+                        density: 1.,
+                        friction: 0.5,
+                    })
+                    .insert(Velocity::from_linear(direction * pattern.speed));
+            }
+        }
+    }
+}
+
+fn enemy_damaged(
+    mut commands: Commands,
+    mut event_reader: EventReader<EnemyDamaged>,
+    mut event_writer: EventWriter<EnemyKilled>,
+    attacker_query: Query<&GlobalTransform>,
+    mut query: Query<(&mut Health, &GlobalTransform, &mut Velocity), With<Enemy>>,
+) {
+    for event in event_reader.iter() {
+        if let Ok((mut health, transform, mut velocity)) = query.get_mut(event.enemy) {
+            // Already dead, the despawn just hasn't happened yet
+            if health.0 == 0 {
+                continue;
+            }
+
+            health.0 -= 1;
+
+            if health.0 == 0 {
+                event_writer.send(EnemyKilled {
+                    enemy: event.enemy,
+                    position: transform.translation,
+                });
+                continue;
+            }
+
+            // Push the enemy directly away from whatever hit it
+            if let Ok(attacker_transform) = attacker_query.get(event.attacker) {
+                let direction =
+                    (transform.translation - attacker_transform.translation).normalize_or_zero();
+                *velocity = Velocity::from_linear(direction * KNOCKBACK_SPEED);
+            }
+
+            commands
+                .entity(event.enemy)
+                .insert(Knockback(Timer::from_seconds(KNOCKBACK_SECONDS, false)));
+        }
+    }
+}
+
+fn enemy_killed(
+    mut commands: Commands,
+    image_assets: Res<ImageAssets>,
+    mut event_reader: EventReader<EnemyKilled>,
+    query: Query<&Loot>,
+) {
+    for event in event_reader.iter() {
+        let coins = query.get(event.enemy).map(|loot| loot.coins).unwrap_or(0);
+
+        commands.entity(event.enemy).despawn_recursive();
+
+        // Scatter the coins in a small circle around where the enemy died
+        for index in 0..coins {
+            let angle = index as f32 / coins as f32 * std::f32::consts::TAU;
+            let offset = Vec3::new(angle.cos(), angle.sin(), 0.) * 6.;
+
+            commands.spawn_bundle(DroppedCoinBundle {
+                pickup: Pickup {
+                    effect: PickupEffect::Coin,
+                },
+
+                sprite_bundle: SpriteBundle {
+                    texture: image_assets.coin.clone(),
+                    transform: Transform::from_translation(event.position + offset),
+                    ..Default::default()
+                },
+
+                collider_bundle: ColliderBundle {
+                    collider: CollisionShape::Cuboid {
+                        half_extends: Vec3::new(4., 4., 0.),
+                        border_radius: None,
+                    },
+                    collision_layers: CollisionLayers::none()
+                        .with_group(GameCollisionLayers::Pickup)
+                        .with_mask(GameCollisionLayers::Player),
+                    rigid_body: RigidBody::Sensor,
+                    rotation_constraints: RotationConstraints::lock(),
+                    ..Default::default()
+                },
+            });
         }
     }
 }
@@ -0,0 +1,99 @@
+use std::collections::HashMap;
+
+use bevy::{
+    asset::{AssetLoader, BoxedFuture, LoadContext, LoadedAsset},
+    reflect::TypeUuid,
+};
+use serde::Deserialize;
+
+use crate::game::game::HEALTH_PER_HEART;
+
+use super::components::ENEMY_DEFAULT_HEALTH;
+
+pub const DEFAULT_ENEMY_KIND: &str = "shaman";
+
+/// How an enemy shoots. `count` projectiles are fanned out evenly over `spread` degrees, centered
+/// on the player.
+#[derive(Deserialize, Clone, Debug)]
+pub struct ProjectilePattern {
+    pub count: u32,
+    pub spread: f32,
+    pub speed: f32,
+    pub time_to_live: f32,
+    /// Health each projectile takes from the player, in half hearts
+    #[serde(default = "default_projectile_damage")]
+    pub damage: u32,
+}
+
+fn default_projectile_damage() -> u32 {
+    HEALTH_PER_HEART
+}
+
+impl Default for ProjectilePattern {
+    fn default() -> Self {
+        Self {
+            count: 1,
+            spread: 0.,
+            speed: 150.,
+            time_to_live: 0.5,
+            damage: default_projectile_damage(),
+        }
+    }
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct EnemyKind {
+    pub sprite_index: usize,
+    pub speed: f32,
+    pub aggro_distance: f32,
+    /// Seconds between shots
+    pub fire_interval: f32,
+    pub health: u32,
+    #[serde(default)]
+    pub coins: u32,
+    #[serde(default)]
+    pub projectile: ProjectilePattern,
+}
+
+impl Default for EnemyKind {
+    fn default() -> Self {
+        Self {
+            sprite_index: 89,
+            speed: 20.,
+            aggro_distance: 100.,
+            fire_interval: 1.,
+            health: ENEMY_DEFAULT_HEALTH,
+            coins: 0,
+            projectile: ProjectilePattern::default(),
+        }
+    }
+}
+
+/// Every enemy kind by name, loaded from `kinds.enemies.ron`.
+#[derive(Deserialize, TypeUuid, Clone, Debug, Default)]
+#[uuid = "5a0d8b8e-3c52-4f0e-9d43-2b8f1a7c6e21"]
+#[serde(transparent)]
+pub struct EnemyKinds(pub HashMap<String, EnemyKind>);
+
+#[derive(Default)]
+pub struct EnemyKindsLoader;
+
+impl AssetLoader for EnemyKindsLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), anyhow::Error>> {
+        Box::pin(async move {
+            let enemy_kinds: EnemyKinds = ron::de::from_bytes(bytes)?;
+            load_context.set_default_asset(LoadedAsset::new(enemy_kinds));
+            Ok(())
+        })
+    }
+
+    /// Bevy matches extensions after each dot of the file name, so the file is named
+    /// `kinds.enemies.ron` for `enemies.ron` to be tried at all.
+    fn extensions(&self) -> &[&str] {
+        &["enemies.ron"]
+    }
+}
@@ -1,3 +1,4 @@
 pub mod components;
 pub mod enemy;
+pub mod kinds;
 pub mod shaman_ai;
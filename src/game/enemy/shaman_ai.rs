@@ -5,7 +5,8 @@ use big_brain::{
 };
 use heron::Velocity;
 
-use crate::game::components::{Enemy, Player};
+use crate::game::components::{Enemy, Knockback, Player, Speed};
+use crate::types::GameState;
 
 use super::components::{Aggroable, Aggroed, AttackPlayer, Attacking};
 
@@ -13,19 +14,35 @@ pub struct ShamanAi;
 
 impl Plugin for ShamanAi {
     fn build(&self, app: &mut App) {
-        app.add_system_to_stage(BigBrainStage::Actions, aggro_system)
-            .add_system_to_stage(BigBrainStage::Scorers, aggro_score_system);
+        // Only think while the game is running so that pausing freezes the shamans too
+        app.add_system_set_to_stage(
+            BigBrainStage::Actions,
+            SystemSet::on_update(GameState::InGame).with_system(aggro_system),
+        )
+        .add_system_set_to_stage(
+            BigBrainStage::Scorers,
+            SystemSet::on_update(GameState::InGame).with_system(aggro_score_system),
+        );
     }
 }
 
 fn aggro_system(
     player_query: Query<&Transform, With<Player>>,
-    mut enemy_query: Query<(&Transform, &mut Velocity, &Aggroable, &mut Attacking)>,
+    mut enemy_query: Query<
+        (
+            &Transform,
+            &mut Velocity,
+            &Speed,
+            &Aggroable,
+            &mut Attacking,
+        ),
+        Without<Knockback>,
+    >,
     mut query: Query<(&Actor, &mut ActionState), With<AttackPlayer>>,
 ) {
     if let Ok(player_transform) = player_query.get_single() {
         for (Actor(actor), mut state) in query.iter_mut() {
-            if let Ok((transform, mut velocity, aggroable, mut attacking)) =
+            if let Ok((transform, mut velocity, speed, aggroable, mut attacking)) =
                 enemy_query.get_mut(*actor)
             {
                 match *state {
@@ -36,7 +53,8 @@ fn aggro_system(
                     ActionState::Executing => {
                         let distance = player_transform.translation - transform.translation;
                         if distance.length().abs() < aggroable.distance.abs() {
-                            *velocity = Velocity::from_linear(distance.normalize_or_zero() * 20.);
+                            *velocity =
+                                Velocity::from_linear(distance.normalize_or_zero() * speed.0);
                         } else {
                             *velocity = Velocity::from_linear(Vec3::ZERO);
                             attacking.is_attacking = false;
@@ -1,11 +1,25 @@
+use bevy::prelude::*;
+
+use super::items::ItemKind;
+
 #[derive(Default, Clone, Debug)]
-pub struct PlayerDamaged;
+pub struct PlayerDamaged {
+    /// Where the hit came from, the player is knocked away from it.
+    pub from: Option<Vec3>,
+    /// In half hearts
+    pub damage: u32,
+}
 
 #[derive(Default, Clone, Debug)]
-pub struct PickupItem;
+pub struct PickupItem {
+    pub kind: ItemKind,
+}
 
 #[derive(Default, Clone, Debug)]
-pub struct PickupCoin;
+pub struct PickupCoin {
+    /// Where the coin was placed in the level. Coins dropped by enemies have none.
+    pub grid: Option<IVec2>,
+}
 
 #[derive(Default, Clone, Debug)]
 pub struct EnemyAttackBlocked;
@@ -15,3 +29,19 @@ pub struct PlayerBorked;
 
 #[derive(Default, Clone, Debug)]
 pub struct GameOver;
+
+/// The player walked into stairs that stay locked until the level's tutorial is done.
+#[derive(Default, Clone, Debug)]
+pub struct StairsLocked;
+
+#[derive(Clone, Debug)]
+pub struct EnemyDamaged {
+    pub enemy: Entity,
+    pub attacker: Entity,
+}
+
+#[derive(Clone, Debug)]
+pub struct EnemyKilled {
+    pub enemy: Entity,
+    pub position: Vec3,
+}
@@ -1,132 +1,347 @@
+use std::collections::{HashMap, HashSet};
+
 use bevy::prelude::*;
 use bevy_ecs_ldtk::prelude::*;
+use heron::Velocity;
+use serde::{Deserialize, Serialize};
 
-use crate::levels::ResetLevel;
+use crate::camera::ScreenShake;
+use crate::levels::{LevelState, LoadLevel, ResetLevel, RestartLevel};
+use crate::save::ContinueRun;
 use crate::types::GameState;
 
 use super::collision::plugin::CollisionPlugin;
-use super::components::TimeToLive;
+use super::components::{GridPosition, Invulnerable, Knockback, Player, TimeToLive};
+use super::dialogue::{DialoguePlugin, DIALOGUE_ENTITIES};
 use super::enemy::enemy::EnemyPlugin;
 use super::events::EnemyAttackBlocked;
+use super::events::EnemyDamaged;
+use super::events::EnemyKilled;
 use super::events::PickupCoin;
 use super::events::PickupItem;
 use super::events::PlayerBorked;
 use super::events::PlayerDamaged;
+use super::events::StairsLocked;
+use super::items::{ItemKind, ItemPlugin};
 use super::level::components::{
-    CoinBundle, EnemyBundle, PlayerBundle, PotionBundle, StairsBundle, WallBundle,
+    DialogueBundle, EnemyBundle, PickupBundle, PlayerBundle, TutorialBundle, WallBundle,
 };
+use super::level::walls::{spawn_wall_colliders, WALL_INT_CELL};
+use super::pickup::{Pickup, PickupEffect, PICKUP_ENTITIES};
 use super::player::PlayerPlugin;
+use super::popups::PopupPlugin;
 use super::sfx::SfxPlugin;
+use super::time::{GameTime, GameTimePlugin};
+use super::tutorial::{TutorialPlugin, TUTORIAL_ENTITY};
 use super::ui::UiPlugin;
 
-pub const PLAYER_MAX_HEALTH: u32 = 3;
+/// Health is counted in half hearts, so hits can take half a heart.
+pub const HEALTH_PER_HEART: u32 = 2;
 
-pub struct GamePlugin;
+pub const PLAYER_MAX_HEALTH: u32 = 3 * HEALTH_PER_HEART;
 
+/// How the player reacts to being hit. Insert a different one before `GamePlugin` to tune it.
 #[derive(Clone, Debug)]
+pub struct PlayerHitSettings {
+    /// Further hits are ignored for this long after taking damage
+    pub invulnerable_seconds: f32,
+    /// How long the sprite stays shown or hidden while blinking
+    pub blink_seconds: f32,
+    pub knockback_speed: f32,
+    pub knockback_seconds: f32,
+    pub flash_seconds: f32,
+}
+
+impl Default for PlayerHitSettings {
+    fn default() -> Self {
+        Self {
+            invulnerable_seconds: 1.5,
+            blink_seconds: 0.1,
+            knockback_speed: 150.,
+            knockback_seconds: 0.15,
+            flash_seconds: 0.3,
+        }
+    }
+}
+
+pub struct GamePlugin;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
 pub struct GameWorldState {
+    /// In half hearts
     pub player_health: u32,
-    pub potion_inventory: u32,
+    /// In half hearts, always a whole number of heart containers
+    pub max_health: u32,
+    /// Potions carried for later, by kind
+    pub inventory: HashMap<ItemKind, u32>,
     pub bork_points: u32,
     pub coins: u32,
+    #[serde(skip)]
     pub is_borking: bool,
     pub play_time: f64,
+    /// Grid positions of the coins picked up in each level, by level index
+    pub collected_coins: HashMap<usize, HashSet<IVec2>>,
 }
 
 impl Default for GameWorldState {
     fn default() -> Self {
         Self {
             player_health: PLAYER_MAX_HEALTH,
-            potion_inventory: 0,
+            max_health: PLAYER_MAX_HEALTH,
+            inventory: HashMap::new(),
             bork_points: 0,
             coins: 0,
             is_borking: false,
             play_time: 0.,
+            collected_coins: HashMap::new(),
         }
     }
 }
 
+impl GameWorldState {
+    pub fn item_count(&self, kind: ItemKind) -> u32 {
+        self.inventory.get(&kind).copied().unwrap_or(0)
+    }
+
+    /// Removes one of the item from the inventory, if there is one.
+    pub fn take_item(&mut self, kind: ItemKind) -> bool {
+        match self.inventory.get_mut(&kind) {
+            Some(count) if *count > 0 => {
+                *count -= 1;
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+/// The world state as it was when the current level started, restored when it is restarted.
+#[derive(Clone, Debug, Default)]
+pub struct LevelStartState(pub GameWorldState);
+
 impl Plugin for GamePlugin {
     fn build(&self, app: &mut App) {
-        app.add_plugin(PlayerPlugin)
+        app.add_plugin(GameTimePlugin)
+            .add_plugin(PlayerPlugin)
+            .add_plugin(ItemPlugin)
             .add_plugin(EnemyPlugin)
             .add_plugin(UiPlugin)
+            .add_plugin(PopupPlugin)
+            .add_plugin(DialoguePlugin)
+            .add_plugin(TutorialPlugin)
             .add_plugin(SfxPlugin)
             .add_plugin(CollisionPlugin)
             .insert_resource(GameWorldState::default())
+            .init_resource::<LevelStartState>()
+            .init_resource::<PlayerHitSettings>()
             .add_system_set(SystemSet::on_enter(GameState::InGame).with_system(reset_game_world))
             .add_system_set(
                 SystemSet::on_update(GameState::InGame)
                     .with_system(player_damaged.label("damage_calculation"))
-                    .with_system(player_picked_up_item)
                     .with_system(player_picked_up_coin)
+                    .with_system(remove_collected_coins)
                     .with_system(time_to_live_system)
-                    .with_system(increase_play_time),
+                    .with_system(knockback_recovery)
+                    .with_system(increase_play_time)
+                    .with_system(remember_level_start)
+                    .with_system(spawn_wall_colliders),
             )
+            .add_system(restore_level_start)
             .register_ldtk_entity::<PlayerBundle>("Player")
-            .register_ldtk_entity::<PotionBundle>("Potion")
-            .register_ldtk_entity::<CoinBundle>("Coin")
-            .register_ldtk_entity::<WallBundle>("Wall")
-            .register_ldtk_entity::<StairsBundle>("Stairs")
             .register_ldtk_entity::<EnemyBundle>("Enemy")
+            .register_ldtk_entity::<TutorialBundle>(TUTORIAL_ENTITY)
+            .register_ldtk_int_cell::<WallBundle>(WALL_INT_CELL)
             .add_event::<PlayerDamaged>()
             .add_event::<PickupItem>()
             .add_event::<PickupCoin>()
             .add_event::<EnemyAttackBlocked>()
-            .add_event::<PlayerBorked>();
+            .add_event::<EnemyDamaged>()
+            .add_event::<EnemyKilled>()
+            .add_event::<PlayerBorked>()
+            .add_event::<StairsLocked>();
+
+        for identifier in PICKUP_ENTITIES {
+            app.register_ldtk_entity::<PickupBundle>(identifier);
+        }
+
+        for identifier in DIALOGUE_ENTITIES {
+            app.register_ldtk_entity::<DialogueBundle>(identifier);
+        }
     }
 }
 
 fn reset_game_world(
+    mut commands: Commands,
     mut game_world_state: ResMut<GameWorldState>,
+    continue_run: Option<Res<ContinueRun>>,
     mut reset_level_event: EventWriter<ResetLevel>,
+    mut load_level_event: EventWriter<LoadLevel>,
 ) {
-    *game_world_state = GameWorldState::default();
-    reset_level_event.send(ResetLevel::default());
+    let save_game = continue_run.map(|continue_run| continue_run.0.clone());
+    commands.remove_resource::<ContinueRun>();
+
+    match save_game {
+        Some(save_game) => {
+            *game_world_state = save_game.world;
+            load_level_event.send(LoadLevel(save_game.current_level));
+        }
+        None => {
+            *game_world_state = GameWorldState::default();
+            reset_level_event.send(ResetLevel::default());
+        }
+    }
+}
+
+fn remember_level_start(
+    level_state: Res<LevelState>,
+    game_world_state: Res<GameWorldState>,
+    mut level_start_state: ResMut<LevelStartState>,
+) {
+    if level_state.is_changed() {
+        level_start_state.0 = game_world_state.clone();
+    }
+}
+
+/// Puts everything back the way it was when the level started, except the play time. Failed
+/// attempts still took that long, and the leaderboard uses the time to break ties.
+fn restore_level_start(
+    mut restart_level_event: EventReader<RestartLevel>,
+    level_start_state: Res<LevelStartState>,
+    mut game_world_state: ResMut<GameWorldState>,
+) {
+    if restart_level_event.iter().count() > 0 {
+        *game_world_state = GameWorldState {
+            play_time: game_world_state.play_time,
+            ..level_start_state.0.clone()
+        };
+    }
 }
 
 fn time_to_live_system(
     mut commands: Commands,
-    time: Res<Time>,
+    game_time: Res<GameTime>,
     mut query: Query<(Entity, &mut TimeToLive)>,
 ) {
     for (entity, mut timer) in query.iter_mut() {
-        if timer.0.tick(time.delta()).finished() {
+        if timer.0.tick(game_time.delta()).finished() {
             commands.entity(entity).despawn();
         }
     }
 }
 
+fn knockback_recovery(
+    mut commands: Commands,
+    game_time: Res<GameTime>,
+    mut query: Query<(Entity, &mut Knockback, &mut Velocity)>,
+) {
+    for (entity, mut knockback, mut velocity) in query.iter_mut() {
+        if knockback.0.tick(game_time.delta()).finished() {
+            *velocity = Velocity::from_linear(Vec3::ZERO);
+            commands.entity(entity).remove::<Knockback>();
+        }
+    }
+}
+
 fn player_damaged(
+    mut commands: Commands,
     mut event_reader: EventReader<PlayerDamaged>,
+    hit_settings: Res<PlayerHitSettings>,
     mut game_world_state: ResMut<GameWorldState>,
     mut game_state: ResMut<State<GameState>>,
+    mut screen_shake_events: EventWriter<ScreenShake>,
+    mut query: Query<
+        (
+            Entity,
+            &GlobalTransform,
+            &mut Velocity,
+            Option<&Invulnerable>,
+        ),
+        With<Player>,
+    >,
 ) {
-    for _ in event_reader.iter() {
-        if game_world_state.player_health > 0 {
-            game_world_state.player_health -= 1;
+    let (player, transform, mut velocity, invulnerable) = match query.get_single_mut() {
+        Ok(player) => player,
+        Err(_) => return,
+    };
+
+    // Only the first hit counts, whether the others come later or in the same frame
+    let mut is_invulnerable = invulnerable.is_some();
+
+    for event in event_reader.iter() {
+        if is_invulnerable {
+            continue;
         }
+        is_invulnerable = true;
+
+        game_world_state.player_health =
+            game_world_state.player_health.saturating_sub(event.damage);
+        screen_shake_events.send(ScreenShake(0.6));
 
         if game_world_state.player_health == 0 {
             game_state.set(GameState::GameOver).ok();
         }
+
+        commands
+            .entity(player)
+            .insert(Invulnerable(Timer::from_seconds(
+                hit_settings.invulnerable_seconds,
+                false,
+            )));
+
+        // Push the player directly away from whatever hit them
+        if let Some(from) = event.from {
+            let direction = (transform.translation - from)
+                .truncate()
+                .normalize_or_zero();
+            *velocity = Velocity::from_linear(direction.extend(0.) * hit_settings.knockback_speed);
+            commands
+                .entity(player)
+                .insert(Knockback(Timer::from_seconds(
+                    hit_settings.knockback_seconds,
+                    false,
+                )));
+        }
     }
 }
 
-fn player_picked_up_item(
-    mut event_reader: EventReader<PickupItem>,
+fn player_picked_up_coin(
+    mut event_reader: EventReader<PickupCoin>,
+    level_state: Res<LevelState>,
     mut game_world_state: ResMut<GameWorldState>,
 ) {
-    game_world_state.bork_points += event_reader.iter().count() as u32;
+    for event in event_reader.iter() {
+        game_world_state.coins += 1;
+
+        if let Some(grid) = event.grid {
+            game_world_state
+                .collected_coins
+                .entry(level_state.current_level)
+                .or_default()
+                .insert(grid);
+        }
+    }
 }
 
-fn player_picked_up_coin(
-    mut event_reader: EventReader<PickupCoin>,
-    mut game_world_state: ResMut<GameWorldState>,
+/// Coins that were already picked up in this run stay gone when their level is loaded again.
+fn remove_collected_coins(
+    mut commands: Commands,
+    level_state: Res<LevelState>,
+    game_world_state: Res<GameWorldState>,
+    query: Query<(Entity, &Pickup, &GridPosition), Added<Pickup>>,
 ) {
-    game_world_state.coins += event_reader.iter().count() as u32;
+    if let Some(collected) = game_world_state
+        .collected_coins
+        .get(&level_state.current_level)
+    {
+        for (entity, pickup, grid_position) in query.iter() {
+            if pickup.effect == PickupEffect::Coin && collected.contains(&grid_position.0) {
+                commands.entity(entity).despawn_recursive();
+            }
+        }
+    }
 }
 
-fn increase_play_time(time: Res<Time>, mut game_world_state: ResMut<GameWorldState>) {
-    game_world_state.play_time += time.delta_seconds_f64();
+fn increase_play_time(game_time: Res<GameTime>, mut game_world_state: ResMut<GameWorldState>) {
+    game_world_state.play_time += game_time.delta_seconds_f64();
 }
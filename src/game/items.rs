@@ -0,0 +1,140 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{input::Action, types::GameState};
+
+use super::{
+    components::{Player, SpeedBoost},
+    events::PickupItem,
+    game::{GameWorldState, HEALTH_PER_HEART},
+    pickup::{Pickup, PickupEffect},
+    time::GameTime,
+};
+
+/// The most hearts the player can collect containers up to.
+pub const MAX_HEART_CONTAINERS: u32 = 8;
+
+const BORK_REFILL_POINTS: u32 = 1;
+
+const SPEED_BOOST_SECONDS: f32 = 8.;
+
+pub const SPEED_BOOST_MULTIPLIER: f32 = 1.5;
+
+/// What a potion does, chosen by the `kind` field of the LDtk entity.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ItemKind {
+    /// Grants a bork point as soon as it is picked up
+    BorkRefill,
+    /// Kept in the inventory, drinking one restores a heart
+    HealingPotion,
+    /// Adds an empty heart container and refills every heart
+    HeartContainer,
+    /// Kept in the inventory, drinking one makes the player faster for a while
+    SpeedBoost,
+}
+
+impl Default for ItemKind {
+    fn default() -> Self {
+        ItemKind::BorkRefill
+    }
+}
+
+impl ItemKind {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "bork" => Some(ItemKind::BorkRefill),
+            "healing" => Some(ItemKind::HealingPotion),
+            "heart_container" => Some(ItemKind::HeartContainer),
+            "speed" => Some(ItemKind::SpeedBoost),
+            _ => None,
+        }
+    }
+
+    /// Index into the `8x8.png` sprite sheet.
+    pub fn sprite_index(&self) -> usize {
+        match self {
+            ItemKind::BorkRefill => 36,
+            ItemKind::HealingPotion => 43,
+            ItemKind::HeartContainer => 44,
+            ItemKind::SpeedBoost => 51,
+        }
+    }
+}
+
+pub struct ItemPlugin;
+
+impl Plugin for ItemPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(
+            SystemSet::on_update(GameState::InGame)
+                .with_system(setup_item)
+                .with_system(player_picked_up_item)
+                .with_system(drink_potion.label("drink_potion"))
+                .with_system(speed_boost_expiry.after("drink_potion")),
+        );
+    }
+}
+
+fn setup_item(mut query: Query<(&Pickup, &mut TextureAtlasSprite), Added<Pickup>>) {
+    for (pickup, mut sprite) in query.iter_mut() {
+        if let PickupEffect::Item(kind) = pickup.effect {
+            sprite.index = kind.sprite_index();
+        }
+    }
+}
+
+fn player_picked_up_item(
+    mut event_reader: EventReader<PickupItem>,
+    mut game_world_state: ResMut<GameWorldState>,
+) {
+    for event in event_reader.iter() {
+        match event.kind {
+            ItemKind::BorkRefill => game_world_state.bork_points += BORK_REFILL_POINTS,
+            ItemKind::HeartContainer => {
+                game_world_state.max_health = (game_world_state.max_health + HEALTH_PER_HEART)
+                    .min(MAX_HEART_CONTAINERS * HEALTH_PER_HEART);
+                game_world_state.player_health = game_world_state.max_health;
+            }
+            ItemKind::HealingPotion | ItemKind::SpeedBoost => {
+                *game_world_state.inventory.entry(event.kind).or_default() += 1;
+            }
+        }
+    }
+}
+
+/// Drinks a healing potion when the player is hurt, and a speed potion otherwise.
+fn drink_potion(
+    mut commands: Commands,
+    actions: Res<Input<Action>>,
+    mut game_world_state: ResMut<GameWorldState>,
+    query: Query<Entity, With<Player>>,
+) {
+    if !actions.just_pressed(Action::Drink) {
+        return;
+    }
+
+    let is_hurt = game_world_state.player_health < game_world_state.max_health;
+
+    if is_hurt && game_world_state.take_item(ItemKind::HealingPotion) {
+        game_world_state.player_health =
+            (game_world_state.player_health + HEALTH_PER_HEART).min(game_world_state.max_health);
+    } else if game_world_state.take_item(ItemKind::SpeedBoost) {
+        for entity in query.iter() {
+            commands
+                .entity(entity)
+                .insert(SpeedBoost(Timer::from_seconds(SPEED_BOOST_SECONDS, false)));
+        }
+    }
+}
+
+fn speed_boost_expiry(
+    mut commands: Commands,
+    game_time: Res<GameTime>,
+    mut query: Query<(Entity, &mut SpeedBoost)>,
+) {
+    for (entity, mut speed_boost) in query.iter_mut() {
+        if speed_boost.0.tick(game_time.delta()).finished() {
+            commands.entity(entity).remove::<SpeedBoost>();
+        }
+    }
+}
@@ -1,12 +1,15 @@
 use crate::camera::CameraFollowing;
 use crate::game::components::*;
-use crate::game::enemy::components::Aggroable;
+use crate::game::dialogue::Dialogue;
+use crate::game::enemy::components::{Aggroable, EnemyArchetype, Health, Loot};
+use crate::game::pickup::Pickup;
+use crate::game::tutorial::TutorialTrigger;
 use crate::texture::components::FacingDirection;
 use bevy::prelude::*;
 use bevy_ecs_ldtk::prelude::*;
 use heron::prelude::*;
 
-#[derive(Bundle, LdtkEntity)]
+#[derive(Default, Bundle, LdtkEntity)]
 pub struct PlayerBundle {
     pub player: Player,
     pub camera_following: CameraFollowing,
@@ -27,6 +30,12 @@ pub struct EnemyBundle {
     pub enemy: Enemy,
     pub aggroable: Aggroable,
     pub facing_direction: FacingDirection,
+    pub speed: Speed,
+    pub health: Health,
+    pub loot: Loot,
+
+    #[from_entity_instance]
+    pub archetype: EnemyArchetype,
 
     #[sprite_sheet_bundle]
     #[bundle]
@@ -37,18 +46,21 @@ pub struct EnemyBundle {
     pub collider_bundle: ColliderBundle,
 }
 
-#[derive(Clone, Debug, Default, Bundle, LdtkEntity)]
+/// Spawned for every wall cell of the `Collisions` layer. The cells are merged into larger
+/// colliders by `spawn_wall_colliders`.
+#[derive(Clone, Debug, Default, Bundle, LdtkIntCell)]
 pub struct WallBundle {
-    wall: Wall,
-
-    #[from_entity_instance]
-    #[bundle]
-    pub collider_bundle: ColliderBundle,
+    pub wall: Wall,
 }
 
+/// Spawned for every LDtk entity in `PICKUP_ENTITIES`.
 #[derive(Clone, Default, Bundle, LdtkEntity)]
-pub struct PotionBundle {
-    pub item: Item,
+pub struct PickupBundle {
+    #[from_entity_instance]
+    pub pickup: Pickup,
+
+    #[from_entity_instance]
+    pub grid_position: GridPosition,
 
     #[sprite_sheet_bundle]
     #[bundle]
@@ -59,35 +71,46 @@ pub struct PotionBundle {
     pub collider_bundle: ColliderBundle,
 }
 
+/// Spawned for every LDtk entity in `DIALOGUE_ENTITIES`. The player walks right up to them, so
+/// they have no collider.
 #[derive(Clone, Default, Bundle, LdtkEntity)]
-pub struct CoinBundle {
-    pub coin: Coin,
+pub struct DialogueBundle {
+    #[from_entity_instance]
+    pub dialogue: Dialogue,
+
+    #[from_entity_instance]
+    pub grid_position: GridPosition,
 
     #[sprite_sheet_bundle]
     #[bundle]
     pub sprite_sheet_bundle: SpriteSheetBundle,
+}
 
+/// Spawned for every `Tutorial` region. It isn't drawn, the player only sees its prompt.
+#[derive(Clone, Default, Bundle, LdtkEntity)]
+pub struct TutorialBundle {
     #[from_entity_instance]
+    pub trigger: TutorialTrigger,
+
     #[bundle]
-    pub collider_bundle: ColliderBundle,
+    pub transform_bundle: TransformBundle,
 }
 
-#[derive(Clone, Default, Bundle, LdtkEntity)]
-pub struct StairsBundle {
-    pub stairs: Stairs,
+#[derive(Bundle)]
+pub struct ProjectileBundle {
+    pub ttl: TimeToLive,
+    pub damage: Damage,
 
-    #[sprite_sheet_bundle]
     #[bundle]
-    pub sprite_sheet_bundle: SpriteSheetBundle,
+    pub sprite_bundle: SpriteBundle,
 
-    #[from_entity_instance]
     #[bundle]
     pub collider_bundle: ColliderBundle,
 }
 
 #[derive(Bundle)]
-pub struct ProjectileBundle {
-    pub ttl: TimeToLive,
+pub struct DroppedCoinBundle {
+    pub pickup: Pickup,
 
     #[bundle]
     pub sprite_bundle: SpriteBundle,
@@ -116,3 +139,79 @@ pub struct ColliderBundle {
     pub velocity: Velocity,
     pub rotation_constraints: RotationConstraints,
 }
+
+impl ColliderBundle {
+    pub fn player() -> Self {
+        ColliderBundle {
+            collider: CollisionShape::Cuboid {
+                half_extends: Vec3::new(4., 4., 0.),
+                border_radius: None,
+            },
+            collision_layers: CollisionLayers::none()
+                .with_group(GameCollisionLayers::Player)
+                .with_masks(&[
+                    GameCollisionLayers::World,
+                    GameCollisionLayers::Enemy,
+                    GameCollisionLayers::EnemyAttack,
+                    GameCollisionLayers::Pickup,
+                ]),
+            rigid_body: RigidBody::Dynamic,
+            rotation_constraints: RotationConstraints::lock(),
+            ..Default::default()
+        }
+    }
+
+    pub fn enemy() -> Self {
+        ColliderBundle {
+            collider: CollisionShape::Cuboid {
+                half_extends: Vec3::new(4., 4., 0.),
+                border_radius: None,
+            },
+            collision_layers: CollisionLayers::none()
+                .with_group(GameCollisionLayers::Enemy)
+                .with_masks(&[
+                    GameCollisionLayers::World,
+                    GameCollisionLayers::Player,
+                    GameCollisionLayers::PlayerAttack,
+                ]),
+            rigid_body: RigidBody::Dynamic,
+            rotation_constraints: RotationConstraints::lock(),
+            ..Default::default()
+        }
+    }
+
+    pub fn pickup() -> Self {
+        ColliderBundle {
+            collider: CollisionShape::Cuboid {
+                half_extends: Vec3::new(4., 4., 0.),
+                border_radius: None,
+            },
+            collision_layers: CollisionLayers::none()
+                .with_group(GameCollisionLayers::Pickup)
+                .with_mask(GameCollisionLayers::Player),
+            rigid_body: RigidBody::Sensor,
+            rotation_constraints: RotationConstraints::lock(),
+            ..Default::default()
+        }
+    }
+
+    pub fn wall(half_extends: Vec2) -> Self {
+        ColliderBundle {
+            collider: CollisionShape::Cuboid {
+                half_extends: half_extends.extend(0.),
+                border_radius: None,
+            },
+            collision_layers: CollisionLayers::none()
+                .with_group(GameCollisionLayers::World)
+                .with_masks(&[
+                    GameCollisionLayers::Player,
+                    GameCollisionLayers::PlayerAttack,
+                    GameCollisionLayers::Enemy,
+                    GameCollisionLayers::EnemyAttack,
+                ]),
+            rigid_body: RigidBody::Static,
+            rotation_constraints: RotationConstraints::lock(),
+            ..Default::default()
+        }
+    }
+}
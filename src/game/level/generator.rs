@@ -0,0 +1,369 @@
+use std::collections::HashSet;
+
+use bevy::math::IVec2;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+use crate::game::items::ItemKind;
+
+use super::walls::merge_into_rects;
+
+const MIN_ROOM_WIDTH: i32 = 6;
+const MAX_ROOM_WIDTH: i32 = 12;
+const MIN_ROOM_HEIGHT: i32 = 5;
+const MAX_ROOM_HEIGHT: i32 = 9;
+
+/// Cells of wall kept between two rooms so they never merge into one.
+const ROOM_GAP: i32 = 2;
+
+/// Room placements tried before settling for fewer rooms.
+const ROOM_ATTEMPTS: usize = 200;
+
+/// Two cells wide so the player doesn't snag on corners.
+const CORRIDOR_WIDTH: i32 = 2;
+
+/// Average enemies per room on the first floor and how many each floor deeper adds.
+const BASE_ENEMIES_PER_ROOM: f32 = 0.5;
+const ENEMIES_PER_ROOM_PER_DEPTH: f32 = 0.35;
+const MAX_ENEMIES_PER_ROOM: f32 = 4.;
+
+const POTION_CHANCE: f64 = 0.35;
+
+/// Enemy kinds from `kinds.enemies.ron` and the first depth they show up at.
+const ENEMY_KINDS: [(&str, usize); 3] = [("shaman", 0), ("sentry", 1), ("elder_shaman", 2)];
+
+/// Indices into the `8x8.png` sprite sheet, as used in `levels.ldtk`.
+pub const PLAYER_SPRITE: usize = 81;
+pub const STAIRS_SPRITE: usize = 31;
+pub const COIN_SPRITE: usize = 43;
+
+/// Something placed on a generated floor, spawned with the same bundle as its LDtk entity.
+#[derive(Clone, Debug, PartialEq)]
+pub enum FloorEntity {
+    Player,
+    Stairs,
+    Coin,
+    Potion(ItemKind),
+    Enemy(&'static str),
+}
+
+/// A generated floor in grid cells, with y pointing up like the world.
+#[derive(Clone, Debug, Default)]
+pub struct Floor {
+    /// Cells the player can walk on
+    pub floor: HashSet<IVec2>,
+    /// Every cell bordering the floor and the sprite sheet index drawn on it
+    pub wall_tiles: Vec<(IVec2, usize)>,
+    /// Wall colliders, each from its lowest to its highest cell
+    pub wall_rects: Vec<(IVec2, IVec2)>,
+    pub entities: Vec<(IVec2, FloorEntity)>,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Room {
+    min: IVec2,
+    max: IVec2,
+}
+
+impl Room {
+    fn center(&self) -> IVec2 {
+        (self.min + self.max) / 2
+    }
+
+    fn is_too_close(&self, other: &Room) -> bool {
+        self.min.x <= other.max.x + ROOM_GAP
+            && other.min.x <= self.max.x + ROOM_GAP
+            && self.min.y <= other.max.y + ROOM_GAP
+            && other.min.y <= self.max.y + ROOM_GAP
+    }
+
+    fn cells(&self) -> impl Iterator<Item = IVec2> + '_ {
+        (self.min.y..=self.max.y)
+            .flat_map(move |y| (self.min.x..=self.max.x).map(move |x| IVec2::new(x, y)))
+    }
+
+    fn random_cell(&self, rng: &mut ChaCha8Rng) -> IVec2 {
+        IVec2::new(
+            rng.gen_range(self.min.x..=self.max.x),
+            rng.gen_range(self.min.y..=self.max.y),
+        )
+    }
+}
+
+/// The area rooms are placed in grows with depth.
+fn floor_size(depth: usize) -> IVec2 {
+    let growth = depth.min(6) as i32;
+    IVec2::new(40 + 6 * growth, 30 + 4 * growth)
+}
+
+fn room_count(depth: usize) -> usize {
+    5 + depth.min(7)
+}
+
+/// Generates the floor at `depth` of the run with `seed`. The same seed and depth always give
+/// the same floor, on every platform.
+pub fn generate_floor(seed: u32, depth: usize) -> Floor {
+    let mut rng = ChaCha8Rng::seed_from_u64((seed as u64) << 32 | depth as u64);
+    let rooms = place_rooms(&mut rng, depth);
+
+    let mut floor = Floor::default();
+    for room in rooms.iter() {
+        floor.floor.extend(room.cells());
+    }
+
+    // Joining each room to the previous one keeps every room reachable
+    for pair in rooms.windows(2) {
+        let horizontal_first = rng.gen_bool(0.5);
+        carve_corridor(
+            &mut floor.floor,
+            pair[0].center(),
+            pair[1].center(),
+            horizontal_first,
+        );
+    }
+
+    let walls = wall_cells(&floor.floor);
+    floor.wall_tiles = walls
+        .iter()
+        .map(|cell| (*cell, wall_tile(&floor.floor, *cell)))
+        .collect();
+    floor.wall_rects = merge_into_rects(&walls);
+    floor.entities = place_entities(&mut rng, &rooms, depth);
+
+    floor
+}
+
+fn place_rooms(rng: &mut ChaCha8Rng, depth: usize) -> Vec<Room> {
+    let size = floor_size(depth);
+    let mut rooms: Vec<Room> = Vec::new();
+
+    for _ in 0..ROOM_ATTEMPTS {
+        if rooms.len() >= room_count(depth) {
+            break;
+        }
+
+        let room_size = IVec2::new(
+            rng.gen_range(MIN_ROOM_WIDTH..=MAX_ROOM_WIDTH),
+            rng.gen_range(MIN_ROOM_HEIGHT..=MAX_ROOM_HEIGHT),
+        );
+        // Leave the outermost cells for walls
+        let min = IVec2::new(
+            rng.gen_range(1..size.x - room_size.x),
+            rng.gen_range(1..size.y - room_size.y),
+        );
+        let room = Room {
+            min,
+            max: min + room_size - IVec2::ONE,
+        };
+
+        if rooms.iter().all(|other| !room.is_too_close(other)) {
+            rooms.push(room);
+        }
+    }
+
+    rooms
+}
+
+/// An L shaped corridor between two cells.
+fn carve_corridor(floor: &mut HashSet<IVec2>, from: IVec2, to: IVec2, horizontal_first: bool) {
+    let corner = if horizontal_first {
+        IVec2::new(to.x, from.y)
+    } else {
+        IVec2::new(from.x, to.y)
+    };
+
+    for (start, end) in [(from, corner), (corner, to)] {
+        let min = start.min(end);
+        let max = start.max(end) + IVec2::splat(CORRIDOR_WIDTH - 1);
+
+        for y in min.y..=max.y {
+            for x in min.x..=max.x {
+                floor.insert(IVec2::new(x, y));
+            }
+        }
+    }
+}
+
+/// Cells next to the floor, sorted so spawning them doesn't depend on hashing.
+fn wall_cells(floor: &HashSet<IVec2>) -> Vec<IVec2> {
+    let mut walls: Vec<IVec2> = floor
+        .iter()
+        .flat_map(|cell| {
+            (-1..=1).flat_map(move |y| (-1..=1).map(move |x| *cell + IVec2::new(x, y)))
+        })
+        .filter(|cell| !floor.contains(cell))
+        .collect::<HashSet<_>>()
+        .into_iter()
+        .collect();
+
+    walls.sort_by_key(|cell| (cell.y, cell.x));
+    walls
+}
+
+/// Picks the edge or corner tile facing the floor, the same ones the levels are drawn with.
+fn wall_tile(floor: &HashSet<IVec2>, cell: IVec2) -> usize {
+    let is_floor = |x: i32, y: i32| floor.contains(&(cell + IVec2::new(x, y)));
+
+    match (
+        is_floor(-1, 0),
+        is_floor(1, 0),
+        is_floor(0, 1),
+        is_floor(0, -1),
+    ) {
+        // Inside corners, where the floor only touches diagonally
+        (false, false, false, false) => {
+            if is_floor(1, -1) {
+                3
+            } else if is_floor(-1, -1) {
+                4
+            } else if is_floor(1, 1) {
+                11
+            } else {
+                12
+            }
+        }
+        // Outside corners
+        (false, true, false, true) => 18,
+        (true, false, false, true) => 16,
+        (false, true, true, false) => 2,
+        (true, false, true, false) => 0,
+        (_, _, _, true) => 17,
+        (_, _, true, _) => 1,
+        (_, true, _, _) => 10,
+        _ => 8,
+    }
+}
+
+fn place_entities(rng: &mut ChaCha8Rng, rooms: &[Room], depth: usize) -> Vec<(IVec2, FloorEntity)> {
+    let mut entities: Vec<(IVec2, FloorEntity)> = Vec::new();
+
+    let (first, last) = match (rooms.first(), rooms.last()) {
+        (Some(first), Some(last)) => (first, last),
+        _ => return entities,
+    };
+
+    entities.push((first.center(), FloorEntity::Player));
+    if rooms.len() > 1 {
+        entities.push((last.center(), FloorEntity::Stairs));
+    } else {
+        place_in_room(rng, &mut entities, last, FloorEntity::Stairs);
+    }
+
+    let enemy_kinds: Vec<&'static str> = ENEMY_KINDS
+        .iter()
+        .filter(|(_, min_depth)| depth >= *min_depth)
+        .map(|(kind, _)| *kind)
+        .collect();
+    let enemies_per_room = (BASE_ENEMIES_PER_ROOM + ENEMIES_PER_ROOM_PER_DEPTH * depth as f32)
+        .min(MAX_ENEMIES_PER_ROOM);
+
+    // The player's room is left empty so nothing shoots them the moment they arrive
+    for room in rooms.iter().skip(1) {
+        for _ in 0..rng.gen_range(1..=3) {
+            place_in_room(rng, &mut entities, room, FloorEntity::Coin);
+        }
+
+        if rng.gen_bool(POTION_CHANCE) {
+            let kind = random_potion(rng);
+            place_in_room(rng, &mut entities, room, FloorEntity::Potion(kind));
+        }
+
+        // Rounding down after adding a random fraction averages out to `enemies_per_room`
+        let enemy_count = (enemies_per_room + rng.gen::<f32>()) as usize;
+        for _ in 0..enemy_count {
+            let kind = enemy_kinds[rng.gen_range(0..enemy_kinds.len())];
+            place_in_room(rng, &mut entities, room, FloorEntity::Enemy(kind));
+        }
+    }
+
+    entities
+}
+
+/// Puts the entity on a free cell of the room.
+fn place_in_room(
+    rng: &mut ChaCha8Rng,
+    entities: &mut Vec<(IVec2, FloorEntity)>,
+    room: &Room,
+    entity: FloorEntity,
+) {
+    // A few tries is plenty, rooms are much larger than what goes in them
+    for _ in 0..10 {
+        let cell = room.random_cell(rng);
+        if entities.iter().all(|(other, _)| *other != cell) {
+            entities.push((cell, entity));
+            return;
+        }
+    }
+}
+
+fn random_potion(rng: &mut ChaCha8Rng) -> ItemKind {
+    match rng.gen_range(0..20) {
+        0..=9 => ItemKind::HealingPotion,
+        10..=14 => ItemKind::SpeedBoost,
+        15..=18 => ItemKind::BorkRefill,
+        _ => ItemKind::HeartContainer,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{generate_floor, FloorEntity};
+
+    const SEEDS: std::ops::Range<u32> = 0..40;
+
+    const DEPTHS: std::ops::Range<usize> = 0..10;
+
+    #[test]
+    fn the_same_seed_and_depth_give_the_same_floor() {
+        for seed in [0, 7, 12345, u32::MAX] {
+            for depth in DEPTHS {
+                let first = generate_floor(seed, depth);
+                let second = generate_floor(seed, depth);
+
+                assert_eq!(first.wall_rects, second.wall_rects);
+                assert_eq!(first.wall_tiles, second.wall_tiles);
+                assert_eq!(first.entities, second.entities);
+            }
+        }
+    }
+
+    #[test]
+    fn other_seeds_give_other_floors() {
+        assert_ne!(generate_floor(1, 0).entities, generate_floor(2, 0).entities);
+    }
+
+    #[test]
+    fn every_floor_has_one_player_and_one_stairs_on_the_floor() {
+        for seed in SEEDS {
+            for depth in DEPTHS {
+                let floor = generate_floor(seed, depth);
+
+                for wanted in [FloorEntity::Player, FloorEntity::Stairs] {
+                    let cells: Vec<_> = floor
+                        .entities
+                        .iter()
+                        .filter(|(_, entity)| *entity == wanted)
+                        .map(|(cell, _)| *cell)
+                        .collect();
+
+                    assert_eq!(
+                        cells.len(),
+                        1,
+                        "seed {} depth {} has {} {:?}",
+                        seed,
+                        depth,
+                        cells.len(),
+                        wanted
+                    );
+                    assert!(
+                        floor.floor.contains(&cells[0]),
+                        "seed {} depth {} has its {:?} off the floor",
+                        seed,
+                        depth,
+                        wanted
+                    );
+                }
+            }
+        }
+    }
+}
@@ -1,137 +1,137 @@
 use bevy::prelude::*;
 use bevy_ecs_ldtk::prelude::*;
-use heron::prelude::*;
 
-use crate::game::components::GameCollisionLayers;
+use crate::game::components::GridPosition;
+use crate::game::dialogue::{paginate, Dialogue};
+use crate::game::enemy::components::EnemyArchetype;
+use crate::game::enemy::kinds::DEFAULT_ENEMY_KIND;
+use crate::game::items::ItemKind;
+use crate::game::pickup::{Pickup, PickupEffect, PICKUP_ENTITIES};
+use crate::game::tutorial::{TutorialStep, TutorialTrigger};
 
 use super::components::ColliderBundle;
 
 impl From<EntityInstance> for ColliderBundle {
     fn from(entity_instance: EntityInstance) -> ColliderBundle {
-        let rotation_constraints = RotationConstraints::lock();
-
         match entity_instance.identifier.as_ref() {
-            "Player" => ColliderBundle {
-                collider: CollisionShape::Cuboid {
-                    half_extends: Vec3::new(4., 4., 0.),
-                    border_radius: None,
-                },
-                collision_layers: CollisionLayers::none()
-                    .with_group(GameCollisionLayers::Player)
-                    .with_masks(&[
-                        GameCollisionLayers::World,
-                        GameCollisionLayers::Enemy,
-                        GameCollisionLayers::EnemyAttack,
-                        GameCollisionLayers::Item,
-                        GameCollisionLayers::Coin,
-                        GameCollisionLayers::Stairs,
-                    ]),
-                rigid_body: RigidBody::Dynamic,
-                rotation_constraints,
-                ..Default::default()
-            },
-            "Enemy" => ColliderBundle {
-                collider: CollisionShape::Cuboid {
-                    half_extends: Vec3::new(4., 4., 0.),
-                    border_radius: None,
-                },
-                collision_layers: CollisionLayers::none()
-                    .with_group(GameCollisionLayers::Enemy)
-                    .with_masks(&[
-                        GameCollisionLayers::World,
-                        GameCollisionLayers::Player,
-                        GameCollisionLayers::PlayerAttack,
-                    ]),
-                rigid_body: RigidBody::Dynamic,
-                rotation_constraints,
-                ..Default::default()
-            },
-            "Potion" => ColliderBundle {
-                collider: CollisionShape::Cuboid {
-                    half_extends: Vec3::new(4., 4., 0.),
-                    border_radius: None,
-                },
-                collision_layers: CollisionLayers::none()
-                    .with_group(GameCollisionLayers::Item)
-                    .with_mask(GameCollisionLayers::Player),
-                rigid_body: RigidBody::Sensor,
-                rotation_constraints,
-                ..Default::default()
-            },
-            "Coin" => ColliderBundle {
-                collider: CollisionShape::Cuboid {
-                    half_extends: Vec3::new(4., 4., 0.),
-                    border_radius: None,
-                },
-                collision_layers: CollisionLayers::none()
-                    .with_group(GameCollisionLayers::Coin)
-                    .with_mask(GameCollisionLayers::Player),
-                rigid_body: RigidBody::Sensor,
-                rotation_constraints,
-                ..Default::default()
+            "Player" => ColliderBundle::player(),
+            "Enemy" => ColliderBundle::enemy(),
+            identifier if PICKUP_ENTITIES.contains(&identifier) => ColliderBundle::pickup(),
+            _ => ColliderBundle::default(),
+        }
+    }
+}
+
+impl From<EntityInstance> for GridPosition {
+    fn from(entity_instance: EntityInstance) -> GridPosition {
+        GridPosition(entity_instance.grid)
+    }
+}
+
+impl From<EntityInstance> for EnemyArchetype {
+    fn from(entity_instance: EntityInstance) -> EnemyArchetype {
+        EnemyArchetype {
+            kind: get_optional_string_from_ldtk(&entity_instance, "kind")
+                .unwrap_or_else(|| DEFAULT_ENEMY_KIND.to_string()),
+            health: get_optional_int_from_ldtk(&entity_instance, "health")
+                .map(|health| health.max(1) as u32),
+            coins: get_optional_int_from_ldtk(&entity_instance, "coins")
+                .map(|coins| coins.max(0) as u32),
+        }
+    }
+}
+
+impl From<EntityInstance> for ItemKind {
+    fn from(entity_instance: EntityInstance) -> ItemKind {
+        let name = match get_optional_string_from_ldtk(&entity_instance, "kind") {
+            Some(name) => name,
+            None => return ItemKind::default(),
+        };
+
+        ItemKind::from_name(&name).unwrap_or_else(|| {
+            warn!("Unknown item kind {}, using the default", name);
+            ItemKind::default()
+        })
+    }
+}
+
+/// Potions are items of their `kind`. Any pickup can instead set its `effect` field.
+impl From<EntityInstance> for Pickup {
+    fn from(entity_instance: EntityInstance) -> Pickup {
+        let effect = match get_optional_string_from_ldtk(&entity_instance, "effect") {
+            Some(name) => PickupEffect::from_name(&name).unwrap_or_else(|| {
+                warn!("Unknown pickup effect {}, using the default", name);
+                PickupEffect::default()
+            }),
+            None => match entity_instance.identifier.as_ref() {
+                "Potion" => PickupEffect::Item(ItemKind::from(entity_instance)),
+                "Stairs" => PickupEffect::Stairs,
+                _ => PickupEffect::Coin,
             },
-            "Stairs" => ColliderBundle {
-                collider: CollisionShape::Cuboid {
-                    half_extends: Vec3::new(4., 4., 0.),
-                    border_radius: None,
-                },
-                collision_layers: CollisionLayers::none()
-                    .with_group(GameCollisionLayers::Stairs)
-                    .with_mask(GameCollisionLayers::Player),
-                rigid_body: RigidBody::Sensor,
-                rotation_constraints,
-                ..Default::default()
+        };
+
+        Pickup { effect }
+    }
+}
+
+/// Signs are read, everyone else is talked to.
+impl From<EntityInstance> for Dialogue {
+    fn from(entity_instance: EntityInstance) -> Dialogue {
+        let text = get_optional_string_from_ldtk(&entity_instance, "text").unwrap_or_default();
+
+        Dialogue {
+            pages: paginate(&text),
+            prompt: match entity_instance.identifier.as_ref() {
+                "Sign" => "Read",
+                _ => "Talk",
             },
-            "Wall" => {
-                let left = get_int_from_ldtk(&entity_instance, "left");
-                let right = get_int_from_ldtk(&entity_instance, "right");
-                let top = get_int_from_ldtk(&entity_instance, "top");
-                let bottom = get_int_from_ldtk(&entity_instance, "bottom");
+        }
+    }
+}
 
-                let left: f32 = (4. + (8 * (left - 1)) as f32) * -1.;
-                let right: f32 = 4. + (8 * (right - 1)) as f32;
-                let top: f32 = 4. + (8 * (top - 1)) as f32;
-                let bottom: f32 = (4. + (8 * (bottom - 1)) as f32) * -1.;
+impl From<EntityInstance> for TutorialTrigger {
+    fn from(entity_instance: EntityInstance) -> TutorialTrigger {
+        let step = match get_optional_string_from_ldtk(&entity_instance, "step") {
+            Some(name) => TutorialStep::from_name(&name).unwrap_or_else(|| {
+                warn!("Unknown tutorial step {}, using the default", name);
+                TutorialStep::default()
+            }),
+            None => TutorialStep::default(),
+        };
 
-                let rotation_constraints = RotationConstraints::lock();
-                return ColliderBundle {
-                    collider: CollisionShape::ConvexHull {
-                        points: vec![
-                            Vec3::new(left, top, 0.),
-                            Vec3::new(right, top, 0.),
-                            Vec3::new(right, bottom, 0.),
-                            Vec3::new(left, bottom, 0.),
-                        ],
-                        border_radius: None,
-                    },
-                    collision_layers: CollisionLayers::none()
-                        .with_group(GameCollisionLayers::World)
-                        .with_masks(&[
-                            GameCollisionLayers::Player,
-                            GameCollisionLayers::PlayerAttack,
-                            GameCollisionLayers::Enemy,
-                            GameCollisionLayers::EnemyAttack,
-                        ]),
-                    rigid_body: RigidBody::Static,
-                    rotation_constraints,
-                    ..Default::default()
-                };
-            }
-            _ => ColliderBundle::default(),
+        TutorialTrigger {
+            step,
+            size: Vec2::new(entity_instance.width as f32, entity_instance.height as f32),
         }
     }
 }
 
-pub fn get_int_from_ldtk(entity_instance: &EntityInstance, field_name: &str) -> i32 {
-    let field = entity_instance
+/// Reads an int field that levels are allowed to leave out.
+pub fn get_optional_int_from_ldtk(
+    entity_instance: &EntityInstance,
+    field_name: &str,
+) -> Option<i32> {
+    entity_instance
         .field_instances
         .iter()
         .find(|f| f.identifier == field_name)
-        .unwrap();
+        .and_then(|field| match &field.value {
+            FieldValue::Int(value) => *value,
+            _ => None,
+        })
+}
 
-    let mut result: i32 = 1;
-    if let FieldValue::Int(value) = &field.value {
-        result = value.unwrap();
-    }
-    return result;
+/// Reads a string field that levels are allowed to leave out.
+pub fn get_optional_string_from_ldtk(
+    entity_instance: &EntityInstance,
+    field_name: &str,
+) -> Option<String> {
+    entity_instance
+        .field_instances
+        .iter()
+        .find(|f| f.identifier == field_name)
+        .and_then(|field| match &field.value {
+            FieldValue::String(value) => value.clone(),
+            _ => None,
+        })
 }
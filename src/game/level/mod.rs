@@ -1,2 +1,5 @@
 pub mod components;
+pub mod generator;
 pub mod ldtk;
+pub mod validation;
+pub mod walls;
@@ -0,0 +1,423 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+use bevy::prelude::*;
+use bevy_ecs_ldtk::ldtk::{LdtkJson, Level};
+use bevy_ecs_ldtk::prelude::*;
+
+use crate::game::dialogue::DIALOGUE_ENTITIES;
+use crate::game::enemy::kinds::EnemyKinds;
+use crate::game::items::ItemKind;
+use crate::game::pickup::{Pickup, PickupEffect, PICKUP_ENTITIES};
+use crate::game::tutorial::{TutorialStep, TUTORIAL_ENTITY};
+use crate::levels::level_name;
+use crate::types::{EnemyAssets, FontAssets, LevelAssets};
+
+use super::ldtk::{get_optional_int_from_ldtk, get_optional_string_from_ldtk};
+use super::walls::WALL_INT_CELL;
+
+const ENTITY_LAYER: &str = "Entities";
+
+const COLLISION_LAYER: &str = "Collisions";
+
+/// Only this many problems are listed on screen, the log has all of them.
+const MAX_LISTED_PROBLEMS: usize = 12;
+
+/// Something in `levels.ldtk` that would make a level spawn wrong or impossible to finish.
+#[derive(Clone, Debug)]
+pub struct LevelProblem {
+    /// The level's name, followed by its identifier when they differ
+    pub level: String,
+    /// The grid cell at fault, for problems with a single entity
+    pub grid: Option<IVec2>,
+    pub message: String,
+}
+
+impl fmt::Display for LevelProblem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.grid {
+            Some(grid) => write!(
+                f,
+                "{} at [{}, {}]: {}",
+                self.level, grid.x, grid.y, self.message
+            ),
+            None => write!(f, "{}: {}", self.level, self.message),
+        }
+    }
+}
+
+#[derive(Component, Default, Clone, Debug)]
+pub struct LevelProblemsText;
+
+/// Checks every level, returning all problems instead of stopping at the first.
+pub fn validate_levels(project: &LdtkJson, enemy_kinds: &EnemyKinds) -> Vec<LevelProblem> {
+    let mut problems = Vec::new();
+
+    for level in project.levels.iter() {
+        validate_level(level, enemy_kinds, &mut problems);
+    }
+
+    problems
+}
+
+fn validate_level(level: &Level, enemy_kinds: &EnemyKinds, problems: &mut Vec<LevelProblem>) {
+    let name = level_name(level);
+    let level_label = if name == level.identifier {
+        name
+    } else {
+        format!("{} ({})", name, level.identifier)
+    };
+
+    let mut report = |grid: Option<IVec2>, message: String| {
+        problems.push(LevelProblem {
+            level: level_label.clone(),
+            grid,
+            message,
+        })
+    };
+
+    let layers = match &level.layer_instances {
+        Some(layers) => layers,
+        None => {
+            report(
+                None,
+                "has no layers, levels must be saved in the project file".into(),
+            );
+            return;
+        }
+    };
+
+    let entity_layer = layers.iter().find(|layer| layer.identifier == ENTITY_LAYER);
+    let collision_layer = layers
+        .iter()
+        .find(|layer| layer.identifier == COLLISION_LAYER);
+
+    if collision_layer.is_none() {
+        report(
+            None,
+            format!("has no {} layer, so it has no walls", COLLISION_LAYER),
+        );
+    }
+
+    let entities = match entity_layer {
+        Some(layer) => &layer.entity_instances,
+        None => {
+            report(None, format!("has no {} layer", ENTITY_LAYER));
+            return;
+        }
+    };
+
+    let is_wall = |grid: IVec2| {
+        collision_layer.map_or(false, |layer| {
+            grid.x >= 0
+                && grid.y >= 0
+                && grid.x < layer.c_wid
+                && grid.y < layer.c_hei
+                && layer
+                    .int_grid_csv
+                    .get((grid.y * layer.c_wid + grid.x) as usize)
+                    == Some(&WALL_INT_CELL)
+        })
+    };
+
+    let mut players = 0;
+    let mut stairs = 0;
+    let mut occupied: HashMap<IVec2, &str> = HashMap::new();
+
+    for entity_instance in entities.iter() {
+        let identifier = entity_instance.identifier.as_str();
+        let grid = entity_instance.grid;
+
+        match identifier {
+            "Player" => players += 1,
+            "Enemy" => {
+                if let Some(kind) = get_optional_string_from_ldtk(entity_instance, "kind") {
+                    if !enemy_kinds.0.contains_key(&kind) {
+                        report(
+                            Some(grid),
+                            format!("Enemy has unknown kind \"{}\", see kinds.enemies.ron", kind),
+                        );
+                    }
+                }
+
+                if let Some(health) = get_optional_int_from_ldtk(entity_instance, "health") {
+                    if health < 1 {
+                        report(
+                            Some(grid),
+                            format!("Enemy health is {}, it must be at least 1", health),
+                        );
+                    }
+                }
+
+                if let Some(coins) = get_optional_int_from_ldtk(entity_instance, "coins") {
+                    if coins < 0 {
+                        report(
+                            Some(grid),
+                            format!("Enemy drops {} coins, it can't be negative", coins),
+                        );
+                    }
+                }
+            }
+            identifier if PICKUP_ENTITIES.contains(&identifier) => {
+                if identifier == "Potion" {
+                    let has_kind = entity_instance
+                        .field_instances
+                        .iter()
+                        .any(|field| field.identifier == "kind");
+
+                    match get_optional_string_from_ldtk(entity_instance, "kind") {
+                        Some(kind) if ItemKind::from_name(&kind).is_none() => {
+                            report(Some(grid), format!("Potion has unknown kind \"{}\"", kind))
+                        }
+                        None if !has_kind => {
+                            report(Some(grid), "Potion is missing its kind field".to_string())
+                        }
+                        _ => {}
+                    }
+                }
+
+                if let Some(effect) = get_optional_string_from_ldtk(entity_instance, "effect") {
+                    if PickupEffect::from_name(&effect).is_none() {
+                        report(
+                            Some(grid),
+                            format!("{} has unknown effect \"{}\"", identifier, effect),
+                        );
+                    }
+                }
+
+                if Pickup::from(entity_instance.clone()).effect == PickupEffect::Stairs {
+                    stairs += 1;
+                }
+            }
+            identifier if DIALOGUE_ENTITIES.contains(&identifier) => {
+                match get_optional_string_from_ldtk(entity_instance, "text") {
+                    Some(text) if !text.trim().is_empty() => {}
+                    _ => report(Some(grid), format!("{} has no text", identifier)),
+                }
+            }
+            TUTORIAL_ENTITY => {
+                match get_optional_string_from_ldtk(entity_instance, "step") {
+                    Some(step) if TutorialStep::from_name(&step).is_none() => report(
+                        Some(grid),
+                        format!("Tutorial has unknown step \"{}\"", step),
+                    ),
+                    None => report(Some(grid), "Tutorial is missing its step".to_string()),
+                    _ => {}
+                }
+
+                // Regions are meant to cover walls and other entities
+                continue;
+            }
+            _ => {
+                report(
+                    Some(grid),
+                    format!("unknown entity {}, it won't be spawned", identifier),
+                );
+                continue;
+            }
+        }
+
+        if is_wall(grid) {
+            report(Some(grid), format!("{} is inside a wall", identifier));
+        }
+
+        if let Some(other) = occupied.insert(grid, identifier) {
+            report(Some(grid), format!("{} overlaps a {}", identifier, other));
+        }
+    }
+
+    match players {
+        0 => report(None, "has no Player".to_string()),
+        1 => {}
+        count => report(None, format!("has {} Players, only one is allowed", count)),
+    }
+
+    if stairs == 0 {
+        report(None, "has no Stairs, so it can't be finished".to_string());
+    }
+}
+
+/// Runs `--validate-levels`, checking the levels in the `assets` directory. Returns the exit
+/// code: 0 when every level is fine, 1 when there are problems and 2 when the files can't be read.
+pub fn run_cli() -> i32 {
+    match validate_files(Path::new("assets")) {
+        Ok(problems) if problems.is_empty() => {
+            println!("All levels are valid");
+            0
+        }
+        Ok(problems) => {
+            for problem in problems.iter() {
+                eprintln!("{}", problem);
+            }
+            eprintln!("Found {} problems", problems.len());
+            1
+        }
+        Err(error) => {
+            eprintln!("Failed to read the levels: {:#}", error);
+            2
+        }
+    }
+}
+
+fn validate_files(assets: &Path) -> anyhow::Result<Vec<LevelProblem>> {
+    let project: LdtkJson = serde_json::from_str(&fs::read_to_string(assets.join("levels.ldtk"))?)?;
+    let enemy_kinds: EnemyKinds =
+        ron::from_str(&fs::read_to_string(assets.join("kinds.enemies.ron"))?)?;
+
+    Ok(validate_levels(&project, &enemy_kinds))
+}
+
+/// Logs every problem once the levels have loaded. Debug builds also list them on screen.
+pub fn validate_loaded_levels(
+    mut commands: Commands,
+    level_assets: Res<LevelAssets>,
+    ldtk_assets: Res<Assets<LdtkAsset>>,
+    enemy_assets: Res<EnemyAssets>,
+    enemy_kinds: Res<Assets<EnemyKinds>>,
+    font_assets: Res<FontAssets>,
+) {
+    let (ldtk_asset, enemy_kinds) = match (
+        ldtk_assets.get(&level_assets.levels),
+        enemy_kinds.get(&enemy_assets.kinds),
+    ) {
+        (Some(ldtk_asset), Some(enemy_kinds)) => (ldtk_asset, enemy_kinds),
+        _ => return,
+    };
+
+    let problems = validate_levels(&ldtk_asset.project, enemy_kinds);
+    for problem in problems.iter() {
+        error!("{}", problem);
+    }
+
+    if cfg!(debug_assertions) && !problems.is_empty() {
+        spawn_problem_list(&mut commands, &font_assets, &problems);
+    }
+}
+
+fn spawn_problem_list(
+    commands: &mut Commands,
+    font_assets: &FontAssets,
+    problems: &[LevelProblem],
+) {
+    let mut lines: Vec<String> = problems
+        .iter()
+        .take(MAX_LISTED_PROBLEMS)
+        .map(|problem| problem.to_string())
+        .collect();
+    if problems.len() > MAX_LISTED_PROBLEMS {
+        lines.push(format!(
+            "and {} more, see the log",
+            problems.len() - MAX_LISTED_PROBLEMS
+        ));
+    }
+
+    commands
+        .spawn_bundle(TextBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: Rect {
+                    left: Val::Px(10.),
+                    top: Val::Px(10.),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            text: Text::with_section(
+                format!("Level problems:\n{}", lines.join("\n")),
+                TextStyle {
+                    font: font_assets.font.clone(),
+                    font_size: 20.,
+                    color: Color::rgb(1., 0.4, 0.4),
+                },
+                Default::default(),
+            ),
+            ..Default::default()
+        })
+        .insert(LevelProblemsText);
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::path::Path;
+
+    use bevy_ecs_ldtk::ldtk::{EntityInstance, LdtkJson};
+
+    use super::{validate_files, validate_levels, LevelProblem, ENTITY_LAYER};
+    use crate::game::enemy::kinds::EnemyKinds;
+
+    fn shipped_levels() -> (LdtkJson, EnemyKinds) {
+        let project = serde_json::from_str(&fs::read_to_string("assets/levels.ldtk").unwrap());
+        let enemy_kinds = ron::from_str(&fs::read_to_string("assets/kinds.enemies.ron").unwrap());
+
+        (project.unwrap(), enemy_kinds.unwrap())
+    }
+
+    /// The entities of the first level, The Kennel.
+    fn first_level_entities(project: &mut LdtkJson) -> &mut Vec<EntityInstance> {
+        &mut project.levels[0]
+            .layer_instances
+            .as_mut()
+            .unwrap()
+            .iter_mut()
+            .find(|layer| layer.identifier == ENTITY_LAYER)
+            .unwrap()
+            .entity_instances
+    }
+
+    fn messages(problems: &[LevelProblem]) -> Vec<String> {
+        problems.iter().map(|problem| problem.to_string()).collect()
+    }
+
+    #[test]
+    fn shipped_levels_are_valid() {
+        let problems = validate_files(Path::new("assets")).unwrap();
+
+        assert!(problems.is_empty(), "{:#?}", messages(&problems));
+    }
+
+    #[test]
+    fn a_level_without_a_player_is_reported() {
+        let (mut project, enemy_kinds) = shipped_levels();
+        first_level_entities(&mut project).retain(|entity| entity.identifier != "Player");
+
+        assert_eq!(
+            messages(&validate_levels(&project, &enemy_kinds)),
+            vec!["The Kennel (Level_0): has no Player"]
+        );
+    }
+
+    #[test]
+    fn a_level_without_stairs_is_reported() {
+        let (mut project, enemy_kinds) = shipped_levels();
+        first_level_entities(&mut project).retain(|entity| entity.identifier != "Stairs");
+
+        assert_eq!(
+            messages(&validate_levels(&project, &enemy_kinds)),
+            vec!["The Kennel (Level_0): has no Stairs, so it can't be finished"]
+        );
+    }
+
+    #[test]
+    fn overlapping_entities_are_reported() {
+        let (mut project, enemy_kinds) = shipped_levels();
+        let entities = first_level_entities(&mut project);
+        let coin = entities
+            .iter()
+            .find(|entity| entity.identifier == "Coin")
+            .unwrap()
+            .clone();
+        let grid = coin.grid;
+        entities.push(coin);
+
+        assert_eq!(
+            messages(&validate_levels(&project, &enemy_kinds)),
+            vec![format!(
+                "The Kennel (Level_0) at [{}, {}]: Coin overlaps a Coin",
+                grid.x, grid.y
+            )]
+        );
+    }
+}
@@ -0,0 +1,183 @@
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+use bevy_ecs_ldtk::prelude::*;
+
+use crate::game::components::Wall;
+
+use super::components::ColliderBundle;
+
+/// The value painted on the `Collisions` IntGrid layer of `levels.ldtk` for walls.
+pub const WALL_INT_CELL: i32 = 1;
+
+/// Size of a grid cell in pixels, the same on every layer.
+pub const GRID_SIZE: f32 = 8.;
+
+/// A static collider over a rectangle of cells, positioned relative to its level.
+#[derive(Bundle)]
+pub struct WallColliderBundle {
+    #[bundle]
+    pub collider_bundle: ColliderBundle,
+
+    #[bundle]
+    pub transform_bundle: TransformBundle,
+}
+
+impl WallColliderBundle {
+    /// Covers the cells from `min` to `max`, both included.
+    pub fn new(min: IVec2, max: IVec2) -> Self {
+        let size = (max - min + IVec2::ONE).as_vec2() * GRID_SIZE;
+        let center = min.as_vec2() * GRID_SIZE + size / 2.;
+
+        Self {
+            collider_bundle: ColliderBundle::wall(size / 2.),
+            transform_bundle: TransformBundle::from_transform(Transform::from_translation(
+                center.extend(0.),
+            )),
+        }
+    }
+}
+
+/// Merges cells into as few rectangles as a row by row sweep finds, so a long wall is one
+/// collider instead of one per cell. `cells` must be sorted by row and then column, debug
+/// builds panic otherwise.
+pub fn merge_into_rects(cells: &[IVec2]) -> Vec<(IVec2, IVec2)> {
+    debug_assert!(
+        cells
+            .windows(2)
+            .all(|pair| (pair[0].y, pair[0].x) < (pair[1].y, pair[1].x)),
+        "wall cells must be sorted by row and then column"
+    );
+
+    // Horizontal runs of cells in each row
+    let mut runs: Vec<(i32, i32, i32)> = Vec::new();
+    for cell in cells {
+        match runs.last_mut() {
+            Some((y, _, end)) if *y == cell.y && *end + 1 == cell.x => *end = cell.x,
+            _ => runs.push((cell.y, cell.x, cell.x)),
+        }
+    }
+
+    // Runs that line up with one ending on the row below grow that rectangle upwards
+    let mut rects: Vec<(IVec2, IVec2)> = Vec::new();
+    for (y, start, end) in runs {
+        let below = rects
+            .iter_mut()
+            .find(|(min, max)| max.y == y - 1 && min.x == start && max.x == end);
+
+        match below {
+            Some((_, max)) => max.y = y,
+            None => rects.push((IVec2::new(start, y), IVec2::new(end, y))),
+        }
+    }
+
+    rects
+}
+
+/// Replaces the wall cells of each newly spawned level with as few colliders as possible. The
+/// colliders are children of the level, so they are despawned along with it.
+pub fn spawn_wall_colliders(
+    mut commands: Commands,
+    wall_query: Query<(&GridCoords, &Parent), Added<Wall>>,
+    parent_query: Query<&Parent>,
+    level_query: Query<Entity, With<Handle<LdtkLevel>>>,
+) {
+    let mut level_walls: HashMap<Entity, Vec<IVec2>> = HashMap::new();
+
+    for (grid_coords, parent) in wall_query.iter() {
+        // Cells sit under their layer rather than directly under the level
+        let mut ancestor = parent.0;
+        while level_query.get(ancestor).is_err() {
+            match parent_query.get(ancestor) {
+                Ok(parent) => ancestor = parent.0,
+                Err(_) => break,
+            }
+        }
+
+        level_walls
+            .entry(ancestor)
+            .or_default()
+            .push(IVec2::new(grid_coords.x, grid_coords.y));
+    }
+
+    for (level, mut cells) in level_walls {
+        cells.sort_by_key(|cell| (cell.y, cell.x));
+
+        commands.entity(level).with_children(|parent| {
+            for (min, max) in merge_into_rects(&cells) {
+                parent.spawn_bundle(WallColliderBundle::new(min, max));
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::*;
+
+    use super::merge_into_rects;
+
+    fn cells(cells: &[(i32, i32)]) -> Vec<IVec2> {
+        cells.iter().map(|(x, y)| IVec2::new(*x, *y)).collect()
+    }
+
+    fn rect(min: (i32, i32), max: (i32, i32)) -> (IVec2, IVec2) {
+        (IVec2::new(min.0, min.1), IVec2::new(max.0, max.1))
+    }
+
+    #[test]
+    fn a_row_is_one_rect() {
+        let row = cells(&[(0, 0), (1, 0), (2, 0), (3, 0)]);
+
+        assert_eq!(merge_into_rects(&row), vec![rect((0, 0), (3, 0))]);
+    }
+
+    #[test]
+    fn a_gap_splits_a_row() {
+        let row = cells(&[(0, 0), (1, 0), (3, 0)]);
+
+        assert_eq!(
+            merge_into_rects(&row),
+            vec![rect((0, 0), (1, 0)), rect((3, 0), (3, 0))]
+        );
+    }
+
+    #[test]
+    fn matching_rows_grow_one_rect() {
+        let block = cells(&[(2, 0), (3, 0), (2, 1), (3, 1), (2, 2), (3, 2)]);
+
+        assert_eq!(merge_into_rects(&block), vec![rect((2, 0), (3, 2))]);
+    }
+
+    #[test]
+    fn an_l_shape_is_two_rects() {
+        let l_shape = cells(&[(0, 0), (1, 0), (2, 0), (0, 1), (0, 2)]);
+
+        assert_eq!(
+            merge_into_rects(&l_shape),
+            vec![rect((0, 0), (2, 0)), rect((0, 1), (0, 2))]
+        );
+    }
+
+    #[test]
+    fn runs_over_different_columns_stay_apart() {
+        let staggered = cells(&[(0, 0), (1, 0), (2, 0), (1, 1), (2, 1), (3, 1)]);
+
+        assert_eq!(
+            merge_into_rects(&staggered),
+            vec![rect((0, 0), (2, 0)), rect((1, 1), (3, 1))]
+        );
+    }
+
+    #[test]
+    fn no_cells_is_no_rects() {
+        assert!(merge_into_rects(&[]).is_empty());
+    }
+
+    #[cfg(debug_assertions)]
+    #[test]
+    #[should_panic(expected = "sorted")]
+    fn unsorted_cells_are_rejected() {
+        merge_into_rects(&cells(&[(0, 1), (0, 0)]));
+    }
+}
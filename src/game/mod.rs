@@ -1,9 +1,15 @@
 pub mod collision;
 pub mod components;
+pub mod dialogue;
 pub mod enemy;
 pub mod events;
 pub mod game;
+pub mod items;
 pub mod level;
+pub mod pickup;
 pub mod player;
+pub mod popups;
 pub mod sfx;
+pub mod time;
+pub mod tutorial;
 pub mod ui;
@@ -0,0 +1,41 @@
+use bevy::prelude::*;
+
+use super::items::ItemKind;
+
+/// LDtk entities that are spawned as a `PickupBundle`. A new collectible only needs its
+/// identifier added here and, if none of the existing effects fit, a new `PickupEffect`.
+pub const PICKUP_ENTITIES: [&str; 3] = ["Potion", "Coin", "Stairs"];
+
+/// What happens when the player touches a pickup. Each effect is handed on as its own event.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PickupEffect {
+    /// Sends `PickupItem`
+    Item(ItemKind),
+    /// Sends `PickupCoin`
+    Coin,
+    /// Sends `IncrementLevel`
+    Stairs,
+}
+
+impl Default for PickupEffect {
+    fn default() -> Self {
+        PickupEffect::Coin
+    }
+}
+
+impl PickupEffect {
+    /// Parses the `effect` field of an LDtk entity, which also accepts any item kind.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "coin" => Some(PickupEffect::Coin),
+            "stairs" => Some(PickupEffect::Stairs),
+            _ => ItemKind::from_name(name).map(PickupEffect::Item),
+        }
+    }
+}
+
+/// Anything the player collects by touching it. It is despawned once its effect has been sent.
+#[derive(Component, Clone, Debug, Default)]
+pub struct Pickup {
+    pub effect: PickupEffect,
+}
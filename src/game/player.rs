@@ -2,15 +2,21 @@ use bevy::prelude::*;
 use heron::prelude::*;
 
 use crate::{
-    camera,
+    camera::{self, ScreenShake},
+    input::{Action, MovementInput},
     types::{GameState, ImageAssets},
 };
 
 use super::{
-    components::{Bork, GameCollisionLayers, Player, Speed, TimeToLive},
+    components::{
+        Bork, GameCollisionLayers, Invulnerable, Knockback, Player, Speed, SpeedBoost, TimeToLive,
+    },
+    dialogue::DialogueState,
     events::PlayerBorked,
-    game::GameWorldState,
+    game::{GameWorldState, PlayerHitSettings},
+    items::SPEED_BOOST_MULTIPLIER,
     level::components::{BorkBundle, ColliderBundle},
+    time::GameTime,
 };
 
 pub struct PlayerPlugin;
@@ -20,13 +26,21 @@ impl Plugin for PlayerPlugin {
         app.add_system_set(
             SystemSet::on_update(GameState::InGame)
                 .with_system(setup_player)
-                .with_system(bork)
-                .with_system(is_borking),
+                // Confirm opens dialogues and shares a button with bork
+                .with_system(bork.after("interact"))
+                .with_system(is_borking)
+                .with_system(blink_while_invulnerable),
         )
         .add_system_set_to_stage(
             CoreStage::Update,
             SystemSet::on_update(GameState::InGame)
-                .with_system(move_player.before(camera::camera_follow_player)),
+                // Knockback replaces the velocity directly but is only marked at the end of the
+                // stage, so moving afterwards would overwrite it
+                .with_system(
+                    move_player
+                        .before(camera::camera_follow_player)
+                        .before("damage_calculation"),
+                ),
         );
     }
 }
@@ -38,25 +52,28 @@ fn setup_player(mut query: Query<&mut Speed, Added<Player>>) {
 }
 
 fn move_player(
-    keyboard_input: Res<Input<KeyCode>>,
-    mut query: Query<(&Speed, &mut Velocity), With<Player>>,
+    movement: Res<MovementInput>,
+    dialogue_state: Res<DialogueState>,
+    // Knockback takes over the velocity for a moment
+    mut query: Query<
+        (&Speed, &mut Velocity, Option<&SpeedBoost>),
+        (With<Player>, Without<Knockback>),
+    >,
 ) {
-    let mut direction = Vec2::default();
-    if keyboard_input.pressed(KeyCode::W) || keyboard_input.pressed(KeyCode::Up) {
-        direction.y = 1.;
-    } else if keyboard_input.pressed(KeyCode::S) || keyboard_input.pressed(KeyCode::Down) {
-        direction.y = -1.;
-    }
-
-    if keyboard_input.pressed(KeyCode::D) || keyboard_input.pressed(KeyCode::Right) {
-        direction.x = 1.;
-    } else if keyboard_input.pressed(KeyCode::A) || keyboard_input.pressed(KeyCode::Left) {
-        direction.x = -1.;
-    }
-
-    for (speed, mut velocity) in query.iter_mut() {
-        let new_velocity =
-            Velocity::from_linear(direction.extend(0.).normalize_or_zero() * speed.0);
+    // Stand still while reading
+    let movement = if dialogue_state.is_open() {
+        Vec2::ZERO
+    } else {
+        movement.0
+    };
+
+    for (speed, mut velocity, speed_boost) in query.iter_mut() {
+        let multiplier = if speed_boost.is_some() {
+            SPEED_BOOST_MULTIPLIER
+        } else {
+            1.
+        };
+        let new_velocity = Velocity::from_linear(movement.extend(0.) * speed.0 * multiplier);
         if velocity.linear != new_velocity.linear {
             *velocity = new_velocity;
         }
@@ -66,12 +83,13 @@ fn move_player(
 fn bork(
     mut commands: Commands,
     mut game_world_state: ResMut<GameWorldState>,
-    keyboard_input: Res<Input<KeyCode>>,
+    actions: Res<Input<Action>>,
     image_assets: Res<ImageAssets>,
     query: Query<Entity, With<Player>>,
     mut event_writer: EventWriter<PlayerBorked>,
+    mut screen_shake_events: EventWriter<ScreenShake>,
 ) {
-    if !keyboard_input.just_pressed(KeyCode::Space) {
+    if !actions.just_pressed(Action::Bork) {
         return;
     }
 
@@ -123,9 +141,28 @@ fn bork(
         commands.entity(entity).push_children(&[child]);
 
         event_writer.send(PlayerBorked::default());
+        screen_shake_events.send(ScreenShake(0.3));
     }
 }
 
 fn is_borking(mut game_world_state: ResMut<GameWorldState>, query: Query<Entity, With<Bork>>) {
     game_world_state.is_borking = query.iter().count() > 0;
 }
+
+fn blink_while_invulnerable(
+    mut commands: Commands,
+    game_time: Res<GameTime>,
+    hit_settings: Res<PlayerHitSettings>,
+    mut query: Query<(Entity, &mut Invulnerable, &mut Visibility), With<Player>>,
+) {
+    for (entity, mut invulnerable, mut visibility) in query.iter_mut() {
+        if invulnerable.0.tick(game_time.delta()).finished() {
+            visibility.is_visible = true;
+            commands.entity(entity).remove::<Invulnerable>();
+            continue;
+        }
+
+        let blinks = (invulnerable.0.elapsed_secs() / hit_settings.blink_seconds) as u32;
+        visibility.is_visible = blinks % 2 == 1;
+    }
+}
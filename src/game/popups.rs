@@ -0,0 +1,164 @@
+use bevy::prelude::*;
+
+use crate::{bitmap_text::BitmapText, types::GameState};
+
+use super::components::Player;
+use super::events::{EnemyDamaged, PickupCoin, PickupItem, StairsLocked};
+use super::items::ItemKind;
+use super::time::GameTime;
+
+/// How long a popup floats before it is gone, fading out over the second half.
+const POPUP_SECONDS: f32 = 0.8;
+
+/// Pixels a popup rises over its lifetime.
+const POPUP_RISE: f32 = 10.;
+
+/// Starts above whatever it is about so it doesn't cover it.
+const POPUP_OFFSET: f32 = 8.;
+
+/// In front of the level and everything on it.
+const POPUP_Z: f32 = 50.;
+
+const DAMAGE_COLOR: Color = Color::rgb(1., 0.35, 0.35);
+
+const COIN_COLOR: Color = Color::rgb(1., 0.85, 0.3);
+
+/// Text floating up from where something happened in the world.
+#[derive(Component, Clone, Debug)]
+pub struct Popup {
+    timer: Timer,
+    start: Vec2,
+}
+
+pub struct PopupPlugin;
+
+impl Plugin for PopupPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(
+            SystemSet::on_update(GameState::InGame)
+                .with_system(enemy_damaged_popup)
+                .with_system(coin_popup)
+                .with_system(item_popup)
+                .with_system(stairs_locked_popup)
+                .with_system(float_popups),
+        )
+        .add_system_set(SystemSet::on_exit(GameState::InGame).with_system(cleanup));
+    }
+}
+
+fn spawn_popup(commands: &mut Commands, position: Vec2, text: BitmapText) {
+    let start = (position + Vec2::new(0., POPUP_OFFSET)).round();
+
+    commands
+        .spawn_bundle(TransformBundle::from_transform(
+            Transform::from_translation(start.extend(POPUP_Z)),
+        ))
+        .insert(text)
+        .insert(Popup {
+            timer: Timer::from_seconds(POPUP_SECONDS, false),
+            start,
+        });
+}
+
+fn enemy_damaged_popup(
+    mut commands: Commands,
+    mut event_reader: EventReader<EnemyDamaged>,
+    query: Query<&GlobalTransform>,
+) {
+    for event in event_reader.iter() {
+        if let Ok(transform) = query.get(event.enemy) {
+            spawn_popup(
+                &mut commands,
+                transform.translation.truncate(),
+                BitmapText::new("-1").with_color(DAMAGE_COLOR),
+            );
+        }
+    }
+}
+
+/// Coins are gone by the time the event is read, so the popup starts at the player instead.
+fn coin_popup(
+    mut commands: Commands,
+    mut event_reader: EventReader<PickupCoin>,
+    query: Query<&GlobalTransform, With<Player>>,
+) {
+    for _ in event_reader.iter() {
+        if let Ok(transform) = query.get_single() {
+            spawn_popup(
+                &mut commands,
+                transform.translation.truncate(),
+                BitmapText::new("+1").with_color(COIN_COLOR),
+            );
+        }
+    }
+}
+
+fn item_popup(
+    mut commands: Commands,
+    mut event_reader: EventReader<PickupItem>,
+    query: Query<&GlobalTransform, With<Player>>,
+) {
+    for event in event_reader.iter() {
+        let label = match event.kind {
+            ItemKind::BorkRefill => "+1 Bork",
+            ItemKind::HealingPotion => "Healing",
+            ItemKind::HeartContainer => "+1 Heart",
+            ItemKind::SpeedBoost => "Speed",
+        };
+
+        if let Ok(transform) = query.get_single() {
+            spawn_popup(
+                &mut commands,
+                transform.translation.truncate(),
+                BitmapText::new(label),
+            );
+        }
+    }
+}
+
+fn stairs_locked_popup(
+    mut commands: Commands,
+    mut event_reader: EventReader<StairsLocked>,
+    query: Query<&GlobalTransform, With<Player>>,
+) {
+    for _ in event_reader.iter() {
+        if let Ok(transform) = query.get_single() {
+            spawn_popup(
+                &mut commands,
+                transform.translation.truncate(),
+                BitmapText::new("Locked"),
+            );
+        }
+    }
+}
+
+fn float_popups(
+    mut commands: Commands,
+    game_time: Res<GameTime>,
+    mut query: Query<(Entity, &mut Popup, &mut Transform, Option<&Children>)>,
+    mut sprite_query: Query<&mut Sprite>,
+) {
+    for (entity, mut popup, mut transform, children) in query.iter_mut() {
+        if popup.timer.tick(game_time.delta()).finished() {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        }
+
+        // Whole pixels, like the camera
+        let rise = (POPUP_RISE * popup.timer.percent()).round();
+        transform.translation.y = popup.start.y + rise;
+
+        let alpha = (popup.timer.percent_left() * 2.).min(1.);
+        for child in children.iter().flat_map(|children| children.iter()) {
+            if let Ok(mut sprite) = sprite_query.get_mut(*child) {
+                sprite.color.set_a(alpha);
+            }
+        }
+    }
+}
+
+fn cleanup(mut commands: Commands, query: Query<Entity, With<Popup>>) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
@@ -1,8 +1,10 @@
 use bevy::prelude::*;
 
+use crate::settings::Settings;
 use crate::types::{AudioAssets, GameState};
 
-use super::events::{EnemyAttackBlocked, PickupCoin, PickupItem, PlayerBorked, PlayerDamaged};
+use super::components::{Invulnerable, Player};
+use super::events::{EnemyAttackBlocked, EnemyDamaged, PickupCoin, PickupItem, PlayerBorked};
 
 pub struct SfxPlugin;
 
@@ -14,6 +16,7 @@ impl Plugin for SfxPlugin {
                 .with_system(player_picked_up_coin_sfx)
                 .with_system(player_damaged_sfx)
                 .with_system(enemy_attack_blocked)
+                .with_system(enemy_damaged_sfx)
                 .with_system(player_borked_sfx),
         );
     }
@@ -23,9 +26,10 @@ fn player_picked_up_item(
     mut event_reader: EventReader<PickupItem>,
     audio_assets: Res<AudioAssets>,
     audio: Res<Audio>,
+    settings: Res<Settings>,
 ) {
     for _ in event_reader.iter() {
-        audio.play(audio_assets.coin.clone());
+        play(&audio, &settings, &audio_assets.coin);
     }
 }
 
@@ -33,19 +37,22 @@ fn player_picked_up_coin_sfx(
     mut event_reader: EventReader<PickupCoin>,
     audio_assets: Res<AudioAssets>,
     audio: Res<Audio>,
+    settings: Res<Settings>,
 ) {
     for _ in event_reader.iter() {
-        audio.play(audio_assets.coin.clone());
+        play(&audio, &settings, &audio_assets.coin);
     }
 }
 
+/// Hits that are ignored while invulnerable stay silent.
 fn player_damaged_sfx(
-    mut event_reader: EventReader<PlayerDamaged>,
+    query: Query<(), (With<Player>, Added<Invulnerable>)>,
     audio_assets: Res<AudioAssets>,
     audio: Res<Audio>,
+    settings: Res<Settings>,
 ) {
-    for _ in event_reader.iter() {
-        audio.play(audio_assets.hit.clone());
+    for _ in query.iter() {
+        play(&audio, &settings, &audio_assets.hit);
     }
 }
 
@@ -53,9 +60,21 @@ fn enemy_attack_blocked(
     mut event_reader: EventReader<EnemyAttackBlocked>,
     audio_assets: Res<AudioAssets>,
     audio: Res<Audio>,
+    settings: Res<Settings>,
+) {
+    for _ in event_reader.iter() {
+        play(&audio, &settings, &audio_assets.block);
+    }
+}
+
+fn enemy_damaged_sfx(
+    mut event_reader: EventReader<EnemyDamaged>,
+    audio_assets: Res<AudioAssets>,
+    audio: Res<Audio>,
+    settings: Res<Settings>,
 ) {
     for _ in event_reader.iter() {
-        audio.play(audio_assets.block.clone());
+        play(&audio, &settings, &audio_assets.hit);
     }
 }
 
@@ -63,8 +82,17 @@ fn player_borked_sfx(
     mut event_reader: EventReader<PlayerBorked>,
     audio_assets: Res<AudioAssets>,
     audio: Res<Audio>,
+    settings: Res<Settings>,
 ) {
     for _ in event_reader.iter() {
-        audio.play(audio_assets.bork.clone());
+        play(&audio, &settings, &audio_assets.bork);
     }
 }
+
+/// Plays a sound once at the volume picked in the settings.
+pub fn play(audio: &Audio, settings: &Settings, sound: &Handle<AudioSource>) {
+    audio.play_with_settings(
+        sound.clone(),
+        PlaybackSettings::ONCE.with_volume(settings.sfx_volume()),
+    );
+}
@@ -0,0 +1,47 @@
+use std::time::Duration;
+
+use bevy::core::CoreSystem;
+use bevy::prelude::*;
+
+/// The step used whenever gameplay has to be reproducible, such as headless runs and replays.
+pub const FIXED_STEP: Duration = Duration::from_nanos(16_666_667);
+
+/// The frame delta that gameplay systems advance by.
+///
+/// This follows `Time` by default. When a fixed step is set every frame advances by exactly that
+/// amount, which keeps a simulated run identical no matter how quickly frames are produced.
+#[derive(Clone, Debug, Default)]
+pub struct GameTime {
+    delta: Duration,
+    fixed_step: Option<Duration>,
+}
+
+impl GameTime {
+    pub fn fixed(step: Duration) -> Self {
+        Self {
+            delta: step,
+            fixed_step: Some(step),
+        }
+    }
+
+    pub fn delta(&self) -> Duration {
+        self.delta
+    }
+
+    pub fn delta_seconds_f64(&self) -> f64 {
+        self.delta.as_secs_f64()
+    }
+}
+
+pub struct GameTimePlugin;
+
+impl Plugin for GameTimePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<GameTime>()
+            .add_system_to_stage(CoreStage::First, update_game_time.after(CoreSystem::Time));
+    }
+}
+
+fn update_game_time(time: Res<Time>, mut game_time: ResMut<GameTime>) {
+    game_time.delta = game_time.fixed_step.unwrap_or_else(|| time.delta());
+}
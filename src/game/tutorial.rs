@@ -0,0 +1,273 @@
+use std::collections::HashSet;
+
+use bevy::prelude::*;
+
+use crate::{
+    bitmap_text::{BitmapText, GLYPH_SIZE},
+    camera::WINDOW_SCALE,
+    input::{Action, InputBindings, MovementInput},
+    types::GameState,
+};
+
+use super::components::{Enemy, Player};
+use super::dialogue::DialogueState;
+use super::events::{EnemyAttackBlocked, PickupCoin, PickupItem, PlayerBorked, StairsLocked};
+use super::time::GameTime;
+
+/// LDtk entity marking a region where the player is shown how to do something. Levels with any
+/// of them keep their stairs locked until every step in them has been done.
+pub const TUTORIAL_ENTITY: &str = "Tutorial";
+
+/// How long the step still to do is shown after walking into locked stairs.
+const REMINDER_SECONDS: f32 = 3.;
+
+/// In the order they are reminded of.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum TutorialStep {
+    Move,
+    Coin,
+    Potion,
+    /// Borking away a shot, or any bork once the level has no enemies left to shoot.
+    Bork,
+}
+
+impl Default for TutorialStep {
+    fn default() -> Self {
+        TutorialStep::Move
+    }
+}
+
+impl TutorialStep {
+    /// Parses the `step` field of a `Tutorial` entity.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "move" => Some(TutorialStep::Move),
+            "coin" => Some(TutorialStep::Coin),
+            "potion" => Some(TutorialStep::Potion),
+            "bork" => Some(TutorialStep::Bork),
+            _ => None,
+        }
+    }
+
+    /// Names the keys or buttons of whichever device is plugged in.
+    fn prompt(&self, bindings: &InputBindings, gamepad: bool) -> String {
+        match self {
+            TutorialStep::Move if gamepad => "Use the stick to move".to_string(),
+            TutorialStep::Move => {
+                let keys = [
+                    Action::MoveUp,
+                    Action::MoveLeft,
+                    Action::MoveDown,
+                    Action::MoveRight,
+                ]
+                .map(|action| bindings.button_name(action, false));
+
+                // WASD reads better without slashes, arrow keys don't
+                let separator = if keys.iter().all(|key| key.len() == 1) {
+                    ""
+                } else {
+                    "/"
+                };
+                format!("Press {} to move", keys.join(separator))
+            }
+            TutorialStep::Coin => "Walk over coins to collect them".to_string(),
+            TutorialStep::Potion => "Bork potions refill your bork".to_string(),
+            TutorialStep::Bork => format!(
+                "Press {} to bork away shots",
+                bindings.button_name(Action::Bork, gamepad)
+            ),
+        }
+    }
+}
+
+/// The region of a `Tutorial` entity, centered on its transform.
+#[derive(Component, Clone, Debug, Default)]
+pub struct TutorialTrigger {
+    pub step: TutorialStep,
+    pub size: Vec2,
+}
+
+impl TutorialTrigger {
+    fn contains(&self, center: Vec2, point: Vec2) -> bool {
+        let offset = (point - center).abs();
+        offset.x <= self.size.x / 2. && offset.y <= self.size.y / 2.
+    }
+}
+
+/// Steps done since the run started. They stay done when the level restarts, as the coins and
+/// potions used for them might not come back.
+#[derive(Default, Clone, Debug)]
+pub struct TutorialProgress {
+    done: HashSet<TutorialStep>,
+    reminder: Option<Timer>,
+}
+
+impl TutorialProgress {
+    /// Whether every step of the level's triggers has been done, which is always the case for
+    /// levels without any.
+    pub fn is_complete<'a>(&self, triggers: impl IntoIterator<Item = &'a TutorialTrigger>) -> bool {
+        triggers
+            .into_iter()
+            .all(|trigger| self.done.contains(&trigger.step))
+    }
+}
+
+#[derive(Component, Default, Clone, Debug)]
+pub struct TutorialPrompt;
+
+#[derive(Component, Default, Clone, Debug)]
+pub struct TutorialPromptText;
+
+pub struct TutorialPlugin;
+
+impl Plugin for TutorialPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<TutorialProgress>()
+            .add_system_set(
+                SystemSet::on_enter(GameState::InGame)
+                    .with_system(reset_progress)
+                    .with_system(spawn_prompt),
+            )
+            .add_system_set(
+                SystemSet::on_update(GameState::InGame)
+                    .with_system(complete_steps.label("complete_tutorial_steps"))
+                    .with_system(remind_of_steps)
+                    .with_system(show_prompt.after("complete_tutorial_steps")),
+            )
+            .add_system_set(SystemSet::on_exit(GameState::InGame).with_system(cleanup));
+    }
+}
+
+fn reset_progress(mut progress: ResMut<TutorialProgress>) {
+    *progress = TutorialProgress::default();
+}
+
+fn spawn_prompt(mut commands: Commands) {
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                size: Size::new(Val::Percent(100.), Val::Auto),
+                position_type: PositionType::Absolute,
+                position: Rect {
+                    bottom: Val::Px(GLYPH_SIZE * WINDOW_SCALE),
+                    ..Default::default()
+                },
+                justify_content: JustifyContent::Center,
+                ..Default::default()
+            },
+            color: Color::NONE.into(),
+            ..Default::default()
+        })
+        .insert(TutorialPrompt)
+        .with_children(|parent| {
+            parent
+                .spawn_bundle(NodeBundle {
+                    color: Color::NONE.into(),
+                    ..Default::default()
+                })
+                .insert(BitmapText::new(""))
+                .insert(TutorialPromptText);
+        });
+}
+
+/// Steps count wherever they are done, not only inside their trigger.
+#[allow(clippy::too_many_arguments)]
+fn complete_steps(
+    movement: Res<MovementInput>,
+    mut progress: ResMut<TutorialProgress>,
+    mut coin_events: EventReader<PickupCoin>,
+    mut item_events: EventReader<PickupItem>,
+    mut bork_events: EventReader<PlayerBorked>,
+    mut blocked_events: EventReader<EnemyAttackBlocked>,
+    player_query: Query<(), With<Player>>,
+    enemy_query: Query<(), With<Enemy>>,
+) {
+    let mut done = Vec::new();
+
+    if movement.0 != Vec2::ZERO && !player_query.is_empty() {
+        done.push(TutorialStep::Move);
+    }
+    if coin_events.iter().count() > 0 {
+        done.push(TutorialStep::Coin);
+    }
+    if item_events.iter().count() > 0 {
+        done.push(TutorialStep::Potion);
+    }
+    // Enemies can be borked to death before they ever shoot, the step mustn't get stuck then
+    let borked = bork_events.iter().count() > 0;
+    if blocked_events.iter().count() > 0 || (borked && enemy_query.is_empty()) {
+        done.push(TutorialStep::Bork);
+    }
+
+    if !done.is_empty() {
+        progress.done.extend(done);
+    }
+}
+
+fn remind_of_steps(
+    game_time: Res<GameTime>,
+    mut progress: ResMut<TutorialProgress>,
+    mut event_reader: EventReader<StairsLocked>,
+) {
+    if event_reader.iter().count() > 0 {
+        progress.reminder = Some(Timer::from_seconds(REMINDER_SECONDS, false));
+    } else if let Some(reminder) = progress.reminder.as_mut() {
+        if reminder.tick(game_time.delta()).finished() {
+            progress.reminder = None;
+        }
+    }
+}
+
+/// Shows the step of the trigger the player stands in, or the first one left after walking into
+/// locked stairs. The dialogue box takes the same spot, so nothing is shown while it is open.
+fn show_prompt(
+    progress: Res<TutorialProgress>,
+    bindings: Res<InputBindings>,
+    gamepads: Res<Gamepads>,
+    dialogue_state: Res<DialogueState>,
+    player_query: Query<&GlobalTransform, With<Player>>,
+    trigger_query: Query<(&TutorialTrigger, &GlobalTransform)>,
+    mut text_query: Query<&mut BitmapText, With<TutorialPromptText>>,
+) {
+    let pending = |trigger: &&TutorialTrigger| !progress.done.contains(&trigger.step);
+
+    let step = match player_query.get_single() {
+        Ok(player) if !dialogue_state.is_open() => {
+            let player = player.translation.truncate();
+
+            trigger_query
+                .iter()
+                .filter(|(trigger, transform)| {
+                    trigger.contains(transform.translation.truncate(), player)
+                })
+                .map(|(trigger, _)| trigger)
+                .find(pending)
+                .or_else(|| {
+                    progress.reminder.as_ref()?;
+                    trigger_query
+                        .iter()
+                        .map(|(trigger, _)| trigger)
+                        .filter(pending)
+                        .min_by_key(|trigger| trigger.step)
+                })
+                .map(|trigger| trigger.step)
+        }
+        _ => None,
+    };
+
+    let value = step
+        .map(|step| step.prompt(&bindings, gamepads.iter().next().is_some()))
+        .unwrap_or_default();
+
+    for mut text in text_query.iter_mut() {
+        if text.value != value {
+            text.value = value.clone();
+        }
+    }
+}
+
+fn cleanup(mut commands: Commands, query: Query<Entity, With<TutorialPrompt>>) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
@@ -1,16 +1,24 @@
 use bevy::prelude::*;
 
 use crate::{
+    bitmap_text::{BitmapText, BitmapTextBundle, HEADING_SCALE},
     camera::WINDOW_SCALE,
+    endless::EndlessRun,
+    levels::LevelState,
     types::{GameState, ImageAssets},
 };
 
-use super::{
-    events::PlayerDamaged,
-    game::{GameWorldState, PLAYER_MAX_HEALTH},
-};
+use super::components::{Invulnerable, Player};
+use super::game::{GameWorldState, PlayerHitSettings, HEALTH_PER_HEART};
+use super::items::ItemKind;
+use super::time::GameTime;
+
+const HEALING_POTION_TINT: Color = Color::rgb(1., 0.45, 0.45);
 
-const MAX_UI_DIGITS: usize = 4;
+const SPEED_POTION_TINT: Color = Color::rgb(0.45, 0.75, 1.);
+
+/// How long the level name stays on screen, the last second of it fading out
+const LEVEL_INTRO_SECONDS: f32 = 2.5;
 
 #[derive(Component, Default, Clone, Debug)]
 pub struct GameUi;
@@ -18,6 +26,10 @@ pub struct GameUi;
 #[derive(Component, Default, Clone, Debug)]
 pub struct UiElementIndex(usize);
 
+/// Holds one `HealthContainerImage` per heart container the player has.
+#[derive(Component, Default, Clone, Debug)]
+pub struct HeartRow;
+
 #[derive(Component, Default, Clone, Debug)]
 pub struct HealthContainerImage;
 
@@ -27,6 +39,18 @@ pub struct BorkPointNumber;
 #[derive(Component, Default, Clone, Debug)]
 pub struct CoinNumber;
 
+/// Text showing how many potions of the kind are carried.
+#[derive(Component, Clone, Debug)]
+pub struct ItemNumber(ItemKind);
+
+#[derive(Component, Default, Clone, Debug)]
+pub struct LevelIntroCard(Timer);
+
+#[derive(Component, Default, Clone, Debug)]
+pub struct ScreenFlash(Timer);
+
+const SCREEN_FLASH_COLOR: Color = Color::rgba(0.8, 0.1, 0.1, 0.5);
+
 pub struct UiPlugin;
 
 impl Plugin for UiPlugin {
@@ -34,9 +58,15 @@ impl Plugin for UiPlugin {
         app.add_system_set(SystemSet::on_enter(GameState::InGame).with_system(spawn_ui))
             .add_system_set(
                 SystemSet::on_update(GameState::InGame)
+                    .with_system(rebuild_heart_row.after("damage_calculation"))
                     .with_system(update_health_containers.after("damage_calculation"))
                     .with_system(update_potion_counter)
-                    .with_system(update_coin_counter),
+                    .with_system(update_coin_counter)
+                    .with_system(update_item_counters)
+                    .with_system(spawn_level_intro.after("change_level"))
+                    .with_system(fade_level_intro)
+                    .with_system(flash_screen)
+                    .with_system(fade_screen_flash),
             )
             .add_system_set(SystemSet::on_exit(GameState::InGame).with_system(cleanup));
     }
@@ -72,23 +102,13 @@ fn spawn_ui(mut commands: Commands, image_assets: Res<ImageAssets>) {
                     ..Default::default()
                 })
                 .with_children(|parent| {
-                    // Hearth Points Counter
-                    for index in 1..=PLAYER_MAX_HEALTH {
-                        parent
-                            .spawn_bundle(ImageBundle {
-                                style: Style {
-                                    size: Size::new(
-                                        Val::Px(8. * WINDOW_SCALE),
-                                        Val::Px(8. * WINDOW_SCALE),
-                                    ),
-                                    ..Default::default()
-                                },
-                                image: image_assets.heart.clone().into(),
-                                ..Default::default()
-                            })
-                            .insert(UiElementIndex(index as usize))
-                            .insert(HealthContainerImage::default());
-                    }
+                    // Hearth Points Counter, filled in to match max health
+                    parent
+                        .spawn_bundle(NodeBundle {
+                            color: Color::NONE.into(),
+                            ..Default::default()
+                        })
+                        .insert(HeartRow::default());
 
                     // Bork Points Counter
                     parent.spawn_bundle(ImageBundle {
@@ -96,25 +116,16 @@ fn spawn_ui(mut commands: Commands, image_assets: Res<ImageAssets>) {
                             size: Size::new(Val::Px(8. * WINDOW_SCALE), Val::Px(8. * WINDOW_SCALE)),
                             ..Default::default()
                         },
-                        image: image_assets.potion.clone().into(),
+                        image: image_assets.bork.clone().into(),
                         ..Default::default()
                     });
-                    for index in 1..=MAX_UI_DIGITS {
-                        parent
-                            .spawn_bundle(ImageBundle {
-                                style: Style {
-                                    size: Size::new(
-                                        Val::Px(8. * WINDOW_SCALE),
-                                        Val::Px(8. * WINDOW_SCALE),
-                                    ),
-                                    ..Default::default()
-                                },
-                                image: image_assets.text0.clone().into(),
-                                ..Default::default()
-                            })
-                            .insert(UiElementIndex(MAX_UI_DIGITS + 1 - index))
-                            .insert(BorkPointNumber::default());
-                    }
+                    parent
+                        .spawn_bundle(NodeBundle {
+                            color: Color::NONE.into(),
+                            ..Default::default()
+                        })
+                        .insert(BitmapText::new("0"))
+                        .insert(BorkPointNumber::default());
 
                     // Coin Counter
                     parent.spawn_bundle(ImageBundle {
@@ -125,100 +136,269 @@ fn spawn_ui(mut commands: Commands, image_assets: Res<ImageAssets>) {
                         image: image_assets.coin.clone().into(),
                         ..Default::default()
                     });
-                    for index in 1..=MAX_UI_DIGITS {
+                    parent
+                        .spawn_bundle(NodeBundle {
+                            color: Color::NONE.into(),
+                            ..Default::default()
+                        })
+                        .insert(BitmapText::new("0"))
+                        .insert(CoinNumber::default());
+
+                    // Carried Potion Counters
+                    for (kind, tint) in [
+                        (ItemKind::HealingPotion, HEALING_POTION_TINT),
+                        (ItemKind::SpeedBoost, SPEED_POTION_TINT),
+                    ] {
+                        parent.spawn_bundle(ImageBundle {
+                            style: Style {
+                                size: Size::new(
+                                    Val::Px(8. * WINDOW_SCALE),
+                                    Val::Px(8. * WINDOW_SCALE),
+                                ),
+                                ..Default::default()
+                            },
+                            image: image_assets.potion.clone().into(),
+                            color: tint.into(),
+                            ..Default::default()
+                        });
                         parent
-                            .spawn_bundle(ImageBundle {
-                                style: Style {
-                                    size: Size::new(
-                                        Val::Px(8. * WINDOW_SCALE),
-                                        Val::Px(8. * WINDOW_SCALE),
-                                    ),
-                                    ..Default::default()
-                                },
-                                image: image_assets.text0.clone().into(),
+                            .spawn_bundle(NodeBundle {
+                                color: Color::NONE.into(),
                                 ..Default::default()
                             })
-                            .insert(UiElementIndex(MAX_UI_DIGITS + 1 - index))
-                            .insert(CoinNumber::default());
+                            .insert(BitmapText::new("0"))
+                            .insert(ItemNumber(kind));
                     }
                 });
         });
 }
 
-fn update_health_containers(
-    game_world_state: Res<GameWorldState>,
-    image_assets: Res<ImageAssets>,
-    mut query: Query<(&mut UiImage, &UiElementIndex), With<HealthContainerImage>>,
-    mut event_reader: EventReader<PlayerDamaged>,
+fn spawn_level_intro(
+    mut commands: Commands,
+    level_state: Res<LevelState>,
+    endless_run: Option<Res<EndlessRun>>,
+    query: Query<Entity, With<LevelIntroCard>>,
 ) {
-    for _ in event_reader.iter() {
-        for (mut image, element_index) in query.iter_mut() {
-            if element_index.0 as u32 > game_world_state.player_health {
-                *image = image_assets.empty_heart.clone().into();
-            } else {
-                *image = image_assets.heart.clone().into();
+    if !level_state.is_changed() {
+        return;
+    }
+
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    let lines = match endless_run {
+        Some(endless_run) => [
+            format!("Floor {}", level_state.current_level + 1),
+            format!("Seed {}", endless_run.seed),
+        ],
+        None => [
+            format!("Level {}", level_state.current_level + 1),
+            level_state.current_level_name().to_string(),
+        ],
+    };
+
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                size: Size::new(Val::Percent(100.), Val::Percent(100.)),
+                position_type: PositionType::Absolute,
+                flex_direction: FlexDirection::ColumnReverse,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..Default::default()
+            },
+            color: Color::NONE.into(),
+            ..Default::default()
+        })
+        .insert(GameUi::default())
+        .insert(LevelIntroCard(Timer::from_seconds(
+            LEVEL_INTRO_SECONDS,
+            false,
+        )))
+        .with_children(|parent| {
+            for line in lines {
+                parent.spawn_bundle(BitmapTextBundle::new(
+                    BitmapText::new(line)
+                        .with_color(Color::WHITE)
+                        .with_scale(HEADING_SCALE),
+                ));
+            }
+        });
+}
+
+fn fade_level_intro(
+    mut commands: Commands,
+    game_time: Res<GameTime>,
+    mut query: Query<(Entity, &mut LevelIntroCard, &Children)>,
+    line_query: Query<&Children, With<BitmapText>>,
+    mut glyph_query: Query<&mut UiColor>,
+) {
+    for (entity, mut card, children) in query.iter_mut() {
+        if card.0.tick(game_time.delta()).finished() {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        }
+
+        // Fading the glyphs themselves, as changing the text's color would lay them out again.
+        // Spaces are already transparent and stay that way.
+        let alpha = (LEVEL_INTRO_SECONDS - card.0.elapsed_secs()).min(1.);
+        for glyphs in children.iter().flat_map(|line| line_query.get(*line)) {
+            for glyph in glyphs.iter() {
+                if let Ok(mut color) = glyph_query.get_mut(*glyph) {
+                    let faded = color.0.a().min(alpha);
+                    color.0.set_a(faded);
+                }
             }
         }
     }
 }
 
-fn update_potion_counter(
+/// Tints the whole screen whenever the player takes damage.
+fn flash_screen(
+    mut commands: Commands,
+    hit_settings: Res<PlayerHitSettings>,
+    query: Query<(), (With<Player>, Added<Invulnerable>)>,
+) {
+    if query.is_empty() {
+        return;
+    }
+
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                size: Size::new(Val::Percent(100.), Val::Percent(100.)),
+                position_type: PositionType::Absolute,
+                ..Default::default()
+            },
+            color: SCREEN_FLASH_COLOR.into(),
+            ..Default::default()
+        })
+        .insert(GameUi::default())
+        .insert(ScreenFlash(Timer::from_seconds(
+            hit_settings.flash_seconds,
+            false,
+        )));
+}
+
+fn fade_screen_flash(
+    mut commands: Commands,
+    game_time: Res<GameTime>,
+    mut query: Query<(Entity, &mut ScreenFlash, &mut UiColor)>,
+) {
+    for (entity, mut flash, mut color) in query.iter_mut() {
+        if flash.0.tick(game_time.delta()).finished() {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        }
+
+        color
+            .0
+            .set_a(SCREEN_FLASH_COLOR.a() * flash.0.percent_left());
+    }
+}
+
+/// Spawns a heart for every container whenever the number of containers changes.
+fn rebuild_heart_row(
+    mut commands: Commands,
     game_world_state: Res<GameWorldState>,
     image_assets: Res<ImageAssets>,
-    mut query: Query<(&mut UiImage, &UiElementIndex), With<BorkPointNumber>>,
+    query: Query<(Entity, Option<&Children>), With<HeartRow>>,
 ) {
-    let digits: Vec<u32> = game_world_state
-        .bork_points
-        .to_string()
-        .chars()
-        .rev()
-        .map(|c| c.to_digit(10).unwrap())
-        .collect();
+    let containers = (game_world_state.max_health / HEALTH_PER_HEART) as usize;
 
-    for (mut image, element_index) in query.iter_mut() {
-        let index = element_index.0;
-        if index >= 1 && index <= MAX_UI_DIGITS {
-            *image = number_to_image(image_assets.clone(), digits.get(index - 1)).into();
+    for (entity, children) in query.iter() {
+        let children = children.map(|children| children.len()).unwrap_or(0);
+        if children == containers {
+            continue;
         }
+
+        commands.entity(entity).despawn_descendants();
+        commands.entity(entity).with_children(|parent| {
+            for index in 1..=containers {
+                parent
+                    .spawn_bundle(ImageBundle {
+                        style: Style {
+                            size: Size::new(Val::Px(8. * WINDOW_SCALE), Val::Px(8. * WINDOW_SCALE)),
+                            ..Default::default()
+                        },
+                        image: heart_image(&image_assets, index, game_world_state.player_health)
+                            .into(),
+                        ..Default::default()
+                    })
+                    .insert(UiElementIndex(index))
+                    .insert(HealthContainerImage::default());
+            }
+        });
     }
 }
 
-fn update_coin_counter(
+fn update_health_containers(
     game_world_state: Res<GameWorldState>,
     image_assets: Res<ImageAssets>,
-    mut query: Query<(&mut UiImage, &UiElementIndex), With<CoinNumber>>,
+    mut query: Query<(&mut UiImage, &UiElementIndex), With<HealthContainerImage>>,
 ) {
-    let digits: Vec<u32> = game_world_state
-        .coins
-        .to_string()
-        .chars()
-        .rev()
-        .map(|c| c.to_digit(10).unwrap())
-        .collect();
+    if !game_world_state.is_changed() {
+        return;
+    }
 
     for (mut image, element_index) in query.iter_mut() {
-        let index = element_index.0;
-        if index >= 1 && index <= MAX_UI_DIGITS {
-            *image = number_to_image(image_assets.clone(), digits.get(index - 1)).into();
-        }
+        *image = heart_image(
+            &image_assets,
+            element_index.0,
+            game_world_state.player_health,
+        )
+        .into();
+    }
+}
+
+/// The image for the container at the 1-based index, given the health in half hearts.
+fn heart_image(image_assets: &ImageAssets, index: usize, health: u32) -> Handle<Image> {
+    let full_at = index as u32 * HEALTH_PER_HEART;
+
+    if health >= full_at {
+        image_assets.heart.clone()
+    } else if health > full_at - HEALTH_PER_HEART {
+        image_assets.half_heart.clone()
+    } else {
+        image_assets.empty_heart.clone()
+    }
+}
+
+fn update_potion_counter(
+    game_world_state: Res<GameWorldState>,
+    mut query: Query<&mut BitmapText, With<BorkPointNumber>>,
+) {
+    for mut text in query.iter_mut() {
+        set_text(&mut text, game_world_state.bork_points.to_string());
+    }
+}
+
+fn update_coin_counter(
+    game_world_state: Res<GameWorldState>,
+    mut query: Query<&mut BitmapText, With<CoinNumber>>,
+) {
+    for mut text in query.iter_mut() {
+        set_text(&mut text, game_world_state.coins.to_string());
+    }
+}
+
+fn update_item_counters(
+    game_world_state: Res<GameWorldState>,
+    mut query: Query<(&mut BitmapText, &ItemNumber)>,
+) {
+    for (mut text, item_number) in query.iter_mut() {
+        set_text(
+            &mut text,
+            game_world_state.item_count(item_number.0).to_string(),
+        );
     }
 }
 
-fn number_to_image(image_assets: ImageAssets, num: Option<&u32>) -> Handle<Image> {
-    let default: u32 = 0;
-    let num = num.unwrap_or(&default);
-    match num {
-        0 => image_assets.text0.clone(),
-        1 => image_assets.text1.clone(),
-        2 => image_assets.text2.clone(),
-        3 => image_assets.text3.clone(),
-        4 => image_assets.text4.clone(),
-        5 => image_assets.text5.clone(),
-        6 => image_assets.text6.clone(),
-        7 => image_assets.text7.clone(),
-        8 => image_assets.text8.clone(),
-        9 => image_assets.text9.clone(),
-        _ => image_assets.text0.clone(),
+/// Only touches the text when it differs, every change lays out the glyphs again.
+fn set_text(text: &mut Mut<BitmapText>, value: String) {
+    if text.value != value {
+        text.value = value;
     }
 }
 
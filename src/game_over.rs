@@ -1,35 +1,104 @@
 use bevy::prelude::*;
 
+use bevy::window::ReceivedCharacter;
+
 use crate::{
+    bitmap_text::{BitmapText, BitmapTextBundle, HEADING_SCALE},
+    endless::EndlessRun,
     game::game::GameWorldState,
-    types::{FontAssets, GameState},
+    input::Action,
+    leaderboard::{Leaderboard, LeaderboardEntry},
+    levels::LevelState,
+    types::GameState,
 };
 
+const MAX_NAME_LENGTH: usize = 12;
+
+const DEFAULT_NAME: &str = "Dog";
+
 #[derive(Component, Default, Clone, Debug)]
 pub struct OnlyInGameOver;
 
+#[derive(Component, Default, Clone, Debug)]
+pub struct NameEntryText;
+
+/// Present while a run that made the leaderboard is waiting for the player's name.
+#[derive(Default, Clone, Debug)]
+pub struct NameEntry {
+    pub name: String,
+}
+
 pub struct GameOverPlugin;
 
+/// Both ways a run can end share this screen, winning only changes the title.
 impl Plugin for GameOverPlugin {
     fn build(&self, app: &mut App) {
-        app.add_system_set(SystemSet::on_enter(GameState::GameOver).with_system(spawn_ui))
-            .add_system_set(SystemSet::on_update(GameState::GameOver).with_system(load_game))
-            .add_system_set(SystemSet::on_exit(GameState::GameOver).with_system(cleanup));
+        for state in [GameState::GameOver, GameState::Victory] {
+            app.add_system_set(
+                SystemSet::on_enter(state.clone())
+                    .with_system(start_name_entry.label("start_name_entry"))
+                    .with_system(spawn_ui.after("start_name_entry")),
+            )
+            .add_system_set(
+                SystemSet::on_update(state.clone())
+                    .with_system(enter_name.label("enter_name"))
+                    .with_system(update_name_entry_text.after("enter_name"))
+                    .with_system(load_game.after("enter_name")),
+            )
+            .add_system_set(SystemSet::on_exit(state).with_system(cleanup));
+        }
+    }
+}
+
+fn start_name_entry(
+    mut commands: Commands,
+    game_world_state: Res<GameWorldState>,
+    mut received_characters: ResMut<Events<ReceivedCharacter>>,
+) {
+    // Whatever was typed while playing, such as holding D when dying, isn't part of the name
+    received_characters.clear();
+
+    if Leaderboard::load().qualifies(game_world_state.coins, game_world_state.play_time) {
+        commands.insert_resource(NameEntry::default());
     }
 }
 
 fn spawn_ui(
     mut commands: Commands,
+    game_state: Res<State<GameState>>,
     game_world_state: Res<GameWorldState>,
-    font_assets: Res<FontAssets>,
+    level_state: Res<LevelState>,
+    endless_run: Option<Res<EndlessRun>>,
 ) {
+    let (title, levels) = if *game_state.current() == GameState::Victory {
+        ("Victory!", level_state.max_levels)
+    } else {
+        ("Game Over", level_state.current_level)
+    };
+
+    // Endless runs show their seed so they can be shared and played again with `--seed`
+    let mut lines = match endless_run {
+        Some(endless_run) => vec![
+            format!("You reached floor {}", level_state.current_level + 1),
+            format!("Seed {}", endless_run.seed),
+        ],
+        None => vec![format!(
+            "You cleared {} of {} levels",
+            levels, level_state.max_levels
+        )],
+    };
+    lines.push(format!("You collected {} coins", game_world_state.coins));
+    lines.push(format!("In {} seconds", game_world_state.play_time as u32));
+    lines.push("Press Enter to Restart".to_string());
+
     commands
         .spawn_bundle(NodeBundle {
             style: Style {
                 size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
                 position_type: PositionType::Absolute,
+                flex_direction: FlexDirection::ColumnReverse,
                 justify_content: JustifyContent::Center,
-                align_items: AlignItems::FlexEnd,
+                align_items: AlignItems::Center,
                 ..Default::default()
             },
             color: Color::NONE.into(),
@@ -37,63 +106,106 @@ fn spawn_ui(
         })
         .insert(OnlyInGameOver)
         .with_children(|parent| {
-            parent.spawn_bundle(TextBundle {
-                style: Style {
-                    align_self: AlignSelf::Center,
-                    ..Default::default()
-                },
-                text: Text {
-                    sections: vec![
-                        TextSection {
-                            value: "Game Over\n".to_string(),
-                            style: TextStyle {
-                                font: font_assets.font.clone(),
-                                font_size: 120.,
-                                color: Color::WHITE,
-                            },
-                        },
-                        TextSection {
-                            value: format!("You collected {} coins\n", game_world_state.coins,)
-                                .to_string(),
-                            style: TextStyle {
-                                font: font_assets.font.clone(),
-                                font_size: 80.,
-                                color: Color::WHITE,
-                            },
-                        },
-                        TextSection {
-                            value: format!("In {} seconds\n", game_world_state.play_time as u32,)
-                                .to_string(),
-                            style: TextStyle {
-                                font: font_assets.font.clone(),
-                                font_size: 80.,
-                                color: Color::WHITE,
-                            },
-                        },
-                        TextSection {
-                            value: "Press Enter to Restart".to_string(),
-                            style: TextStyle {
-                                font: font_assets.font.clone(),
-                                font_size: 80.,
-                                color: Color::WHITE,
-                            },
-                        },
-                    ],
-                    alignment: TextAlignment {
-                        vertical: VerticalAlign::Center,
-                        horizontal: HorizontalAlign::Center,
-                        ..Default::default()
-                    },
-                    ..Default::default()
-                },
-                ..Default::default()
-            });
+            parent.spawn_bundle(BitmapTextBundle::new(
+                BitmapText::new(title)
+                    .with_color(Color::WHITE)
+                    .with_scale(HEADING_SCALE),
+            ));
+
+            for line in lines {
+                parent.spawn_bundle(BitmapTextBundle::new(
+                    BitmapText::new(line).with_color(Color::WHITE),
+                ));
+            }
         });
+
+    let mut name_entry_text = BitmapTextBundle::new(BitmapText::new("").with_color(Color::WHITE));
+    name_entry_text.node.style = Style {
+        position_type: PositionType::Absolute,
+        position: Rect {
+            left: Val::Px(20.),
+            bottom: Val::Px(20.),
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+
+    commands
+        .spawn_bundle(name_entry_text)
+        .insert(OnlyInGameOver)
+        .insert(NameEntryText);
 }
 
-fn load_game(keyboard_input: Res<Input<KeyCode>>, mut game_state: ResMut<State<GameState>>) {
-    if keyboard_input.just_released(KeyCode::Space) || keyboard_input.just_released(KeyCode::Return)
+fn enter_name(
+    mut commands: Commands,
+    name_entry: Option<ResMut<NameEntry>>,
+    actions: Res<Input<Action>>,
+    keyboard_input: Res<Input<KeyCode>>,
+    mut received_characters: EventReader<ReceivedCharacter>,
+    game_world_state: Res<GameWorldState>,
+) {
+    let mut name_entry = match name_entry {
+        Some(name_entry) => name_entry,
+        None => return,
+    };
+
+    for event in received_characters.iter() {
+        if (event.char.is_ascii_alphanumeric() || event.char == ' ')
+            && name_entry.name.len() < MAX_NAME_LENGTH
+        {
+            name_entry.name.push(event.char);
+        }
+    }
+
+    if keyboard_input.just_pressed(KeyCode::Back) {
+        name_entry.name.pop();
+    }
+
+    // Space is part of the name here, so only other Confirm bindings such as a gamepad work
+    if keyboard_input.just_released(KeyCode::Return)
+        || (actions.just_released(Action::Confirm) && !keyboard_input.just_released(KeyCode::Space))
     {
+        let name = name_entry.name.trim();
+
+        let mut leaderboard = Leaderboard::load();
+        leaderboard.insert(LeaderboardEntry {
+            name: if name.is_empty() { DEFAULT_NAME } else { name }.to_string(),
+            coins: game_world_state.coins,
+            play_time: game_world_state.play_time,
+        });
+        leaderboard.save();
+
+        // Restarting checks for this resource, so the same press can't also restart the game
+        commands.remove_resource::<NameEntry>();
+    }
+}
+
+fn update_name_entry_text(
+    name_entry: Option<Res<NameEntry>>,
+    mut query: Query<&mut BitmapText, With<NameEntryText>>,
+) {
+    let value = match name_entry {
+        Some(name_entry) => format!("New high score! Name: {}_", name_entry.name),
+        None => String::new(),
+    };
+
+    for mut text in query.iter_mut() {
+        if text.value != value {
+            text.value = value.clone();
+        }
+    }
+}
+
+fn load_game(
+    actions: Res<Input<Action>>,
+    name_entry: Option<Res<NameEntry>>,
+    mut game_state: ResMut<State<GameState>>,
+) {
+    if name_entry.is_some() {
+        return;
+    }
+
+    if actions.just_released(Action::Confirm) {
         game_state.set(GameState::InGame).unwrap();
     }
 }
@@ -102,4 +214,6 @@ fn cleanup(mut commands: Commands, query: Query<Entity, With<OnlyInGameOver>>) {
     for entity in query.iter() {
         commands.entity(entity).despawn_recursive();
     }
+
+    commands.remove_resource::<NameEntry>();
 }
@@ -0,0 +1,297 @@
+use std::time::Duration;
+
+use bevy::input::{keyboard::KeyboardInput, ElementState};
+use bevy::prelude::*;
+use bevy::render::settings::WgpuSettings;
+use bevy::winit::WinitPlugin;
+use heron::PhysicsSteps;
+
+use crate::game::components::Player;
+use crate::game::game::GameWorldState;
+use crate::game::time::{GameTime, FIXED_STEP};
+use crate::input::InputBindings;
+use crate::storage;
+use crate::types::GameState;
+
+/// Upper bound on frames spent waiting for assets or the level before giving up.
+const MAX_WAIT_FRAMES: usize = 10_000;
+
+/// The full game running without a window, audio output device or GPU.
+///
+/// Assets are read from the `assets` directory on disk instead of being embedded, and both
+/// gameplay and heron physics advance by `FIXED_STEP` per frame so a scripted run always ends
+/// in the same `GameWorldState`. Storage is turned off and the default bindings are used, so
+/// nothing on the machine running it changes the outcome and no saves are written.
+///
+/// It is built on `DefaultPlugins` rather than `MinimalPlugins`. The asset collections need the
+/// loaders and asset types for images, texture atlases, fonts and audio, and the game's systems
+/// query sprite and UI components. Those come with the render, sprite, UI, text and audio
+/// plugins. The renderer is given no backends, so none of them needs a GPU. Only the window is
+/// left out.
+pub struct HeadlessGame {
+    app: App,
+}
+
+impl HeadlessGame {
+    /// Loads every asset collection and `levels.ldtk`, then enters `GameState::InGame` and waits
+    /// for the player to be spawned.
+    pub fn new() -> Self {
+        storage::disable();
+
+        let mut app = App::new();
+
+        app.insert_resource(InputBindings::default())
+            .insert_resource(WgpuSettings {
+                backends: None,
+                ..Default::default()
+            })
+            .insert_resource(GameTime::fixed(FIXED_STEP))
+            .insert_resource(PhysicsSteps::every_frame(FIXED_STEP))
+            .add_plugins_with(DefaultPlugins, |group| group.disable::<WinitPlugin>());
+
+        crate::build_game(&mut app);
+
+        let mut game = Self { app };
+
+        game.wait_until("assets to load", |world| {
+            *world.resource::<State<GameState>>().current() == GameState::MainMenu
+        });
+
+        game.app
+            .world
+            .resource_mut::<State<GameState>>()
+            .set(GameState::InGame)
+            .unwrap();
+
+        game.wait_until("the player to spawn", |world| {
+            world
+                .query_filtered::<Entity, With<Player>>()
+                .iter(world)
+                .next()
+                .is_some()
+        });
+
+        game
+    }
+
+    pub fn step(&mut self, frames: usize) {
+        for _ in 0..frames {
+            self.app.update();
+        }
+    }
+
+    pub fn press(&mut self, key: KeyCode) {
+        self.send_key(key, ElementState::Pressed);
+    }
+
+    pub fn release(&mut self, key: KeyCode) {
+        self.send_key(key, ElementState::Released);
+    }
+
+    /// Holds the keys down for the given number of frames and releases them afterwards.
+    pub fn hold(&mut self, keys: &[KeyCode], frames: usize) {
+        for key in keys {
+            self.press(*key);
+        }
+
+        self.step(frames);
+
+        for key in keys {
+            self.release(*key);
+        }
+    }
+
+    pub fn world_state(&self) -> &GameWorldState {
+        self.app.world.resource::<GameWorldState>()
+    }
+
+    pub fn game_state(&self) -> GameState {
+        self.app
+            .world
+            .resource::<State<GameState>>()
+            .current()
+            .clone()
+    }
+
+    fn send_key(&mut self, key: KeyCode, state: ElementState) {
+        self.app
+            .world
+            .resource_mut::<Events<KeyboardInput>>()
+            .send(KeyboardInput {
+                scan_code: 0,
+                key_code: Some(key),
+                state,
+            });
+    }
+
+    fn wait_until(&mut self, description: &str, condition: fn(&mut World) -> bool) {
+        for _ in 0..MAX_WAIT_FRAMES {
+            if condition(&mut self.app.world) {
+                return;
+            }
+
+            self.app.update();
+
+            // Give the asset server's background tasks a chance to make progress
+            std::thread::sleep(Duration::from_millis(1));
+        }
+
+        panic!("Timed out waiting for {}", description);
+    }
+}
+
+/// Runs a headless game for the given number of frames while holding the keys, then prints the
+/// resulting state.
+pub fn run(frames: usize, keys: &[KeyCode]) {
+    let mut game = HeadlessGame::new();
+
+    game.hold(keys, frames);
+    game.step(1);
+
+    println!("{:?}", game.game_state());
+    println!("{:#?}", game.world_state());
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::*;
+
+    use super::HeadlessGame;
+    use crate::game::components::{Enemy, GridPosition, Player};
+    use crate::game::events::PlayerDamaged;
+    use crate::game::game::HEALTH_PER_HEART;
+    use crate::game::pickup::{Pickup, PickupEffect};
+    use crate::game::tutorial::TutorialTrigger;
+    use crate::levels::LevelState;
+    use crate::types::GameState;
+
+    /// Enough to cross all of Level_0.
+    const MAX_FRAMES: usize = 60 * 20;
+
+    const MOVE_KEYS: [KeyCode; 4] = [KeyCode::W, KeyCode::A, KeyCode::S, KeyCode::D];
+
+    /// Removes the enemies, so nothing gets in the way of a walk, and the tutorial regions that
+    /// keep Level_0's stairs locked.
+    fn clear_level(game: &mut HeadlessGame) {
+        let world = &mut game.app.world;
+        let entities: Vec<Entity> = world
+            .query_filtered::<Entity, Or<(With<Enemy>, With<TutorialTrigger>)>>()
+            .iter(world)
+            .collect();
+
+        for entity in entities {
+            world.entity_mut(entity).despawn_recursive();
+        }
+    }
+
+    fn player_position(game: &mut HeadlessGame) -> Option<Vec2> {
+        let world = &mut game.app.world;
+        world
+            .query_filtered::<&GlobalTransform, With<Player>>()
+            .iter(world)
+            .next()
+            .map(|transform| transform.translation.truncate())
+    }
+
+    fn pickup_position(
+        game: &mut HeadlessGame,
+        matches: impl Fn(&Pickup, &GridPosition) -> bool,
+    ) -> Vec2 {
+        let world = &mut game.app.world;
+        world
+            .query::<(&Pickup, &GridPosition, &GlobalTransform)>()
+            .iter(world)
+            .find(|(pickup, grid, _)| matches(pickup, grid))
+            .map(|(_, _, transform)| transform.translation.truncate())
+            .expect("Level_0 has no such pickup")
+    }
+
+    /// Holds the keys a player would to reach `target`, first up or down and then across, until
+    /// `done` holds. Level_0 is open enough for that to get anywhere these tests go.
+    fn walk_to(game: &mut HeadlessGame, target: Vec2, done: impl Fn(&HeadlessGame) -> bool) {
+        for _ in 0..MAX_FRAMES {
+            if done(game) {
+                break;
+            }
+
+            let offset = match player_position(game) {
+                Some(player) => target - player,
+                None => break,
+            };
+            let key = if offset.y.abs() > 2. {
+                if offset.y > 0. {
+                    KeyCode::W
+                } else {
+                    KeyCode::S
+                }
+            } else if offset.x > 0. {
+                KeyCode::D
+            } else {
+                KeyCode::A
+            };
+
+            for other in MOVE_KEYS.into_iter().filter(|other| *other != key) {
+                game.release(other);
+            }
+            game.press(key);
+            game.step(1);
+        }
+
+        for key in MOVE_KEYS {
+            game.release(key);
+        }
+        game.step(1);
+    }
+
+    #[test]
+    fn walking_onto_a_coin_collects_it() {
+        let mut game = HeadlessGame::new();
+        clear_level(&mut game);
+        assert_eq!(game.world_state().coins, 0);
+
+        let coin = pickup_position(&mut game, |pickup, grid| {
+            pickup.effect == PickupEffect::Coin && grid.0 == IVec2::new(45, 28)
+        });
+        walk_to(&mut game, coin, |game| game.world_state().coins > 0);
+
+        assert_eq!(game.world_state().coins, 1);
+    }
+
+    #[test]
+    fn taking_the_stairs_starts_the_next_level() {
+        let mut game = HeadlessGame::new();
+        clear_level(&mut game);
+
+        let stairs = pickup_position(&mut game, |pickup, _| pickup.effect == PickupEffect::Stairs);
+        walk_to(&mut game, stairs, |game| {
+            game.app.world.resource::<LevelState>().current_level != 0
+        });
+
+        assert_eq!(game.app.world.resource::<LevelState>().current_level, 1);
+        assert_eq!(game.game_state(), GameState::InGame);
+    }
+
+    #[test]
+    fn running_out_of_health_ends_the_game() {
+        let mut game = HeadlessGame::new();
+
+        // Each hit is followed by a moment of invulnerability, so keep hitting until it runs out
+        for _ in 0..MAX_FRAMES {
+            if game.game_state() == GameState::GameOver {
+                break;
+            }
+
+            game.app
+                .world
+                .resource_mut::<Events<PlayerDamaged>>()
+                .send(PlayerDamaged {
+                    from: None,
+                    damage: HEALTH_PER_HEART,
+                });
+            game.step(1);
+        }
+
+        assert_eq!(game.game_state(), GameState::GameOver);
+        assert_eq!(game.world_state().player_health, 0);
+    }
+}
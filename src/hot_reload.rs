@@ -0,0 +1,154 @@
+use bevy::prelude::*;
+use bevy_ecs_ldtk::ldtk::LdtkJson;
+use bevy_ecs_ldtk::prelude::*;
+
+use crate::endless::EndlessRun;
+use crate::game::components::Player;
+use crate::game::enemy::kinds::EnemyKinds;
+use crate::game::level::validation::validate_levels;
+use crate::game::level::walls::{GRID_SIZE, WALL_INT_CELL};
+use crate::levels::{read_level_list, LevelState};
+use crate::types::{EnemyAssets, GameState, LevelAssets};
+
+/// Respawns the current level whenever `levels.ldtk` is saved. Only built with the `dev` feature,
+/// which reads assets from disk and watches them for changes instead of embedding them.
+pub struct HotReloadPlugin;
+
+/// Where the player stood before the level was reloaded, relative to the level. Only applies to
+/// the player of that level in the world spawned by the reload, as the reloaded level might not
+/// have a player anymore.
+struct ReloadedPlayerPosition {
+    world: Entity,
+    level: usize,
+    position: Vec2,
+}
+
+impl Plugin for HotReloadPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(
+            SystemSet::on_update(GameState::InGame)
+                .with_system(reload_levels)
+                .with_system(restore_player_position),
+        );
+    }
+}
+
+/// Unlike restarting, this keeps `GameWorldState` so collected coins stay collected.
+#[allow(clippy::too_many_arguments)]
+fn reload_levels(
+    mut commands: Commands,
+    mut ldtk_events: EventReader<AssetEvent<LdtkAsset>>,
+    level_assets: Res<LevelAssets>,
+    ldtk_assets: Res<Assets<LdtkAsset>>,
+    enemy_assets: Res<EnemyAssets>,
+    enemy_kinds: Res<Assets<EnemyKinds>>,
+    mut level_state: ResMut<LevelState>,
+    endless_run: Option<Res<EndlessRun>>,
+    world_query: Query<Entity, With<LevelSet>>,
+    player_query: Query<&Transform, With<Player>>,
+) {
+    let modified = ldtk_events.iter().any(|event| match event {
+        AssetEvent::Modified { handle } => *handle == level_assets.levels,
+        _ => false,
+    });
+    if !modified {
+        return;
+    }
+
+    let project = match ldtk_assets.get(&level_assets.levels) {
+        Some(ldtk_asset) => &ldtk_asset.project,
+        None => return,
+    };
+
+    info!("levels.ldtk changed, reloading");
+    if let Some(enemy_kinds) = enemy_kinds.get(&enemy_assets.kinds) {
+        for problem in validate_levels(project, enemy_kinds) {
+            error!("{}", problem);
+        }
+    }
+    read_level_list(&mut level_state, project);
+
+    // Generated floors don't come from the file
+    if endless_run.is_some() {
+        return;
+    }
+
+    for entity in world_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    let world = commands
+        .spawn_bundle(LdtkWorldBundle {
+            ldtk_handle: level_assets.levels.clone(),
+            ..Default::default()
+        })
+        .id();
+
+    commands.remove_resource::<ReloadedPlayerPosition>();
+    if let Ok(player_transform) = player_query.get_single() {
+        let position = player_transform.translation.truncate();
+        if !is_wall(project, level_state.current_level, position) {
+            commands.insert_resource(ReloadedPlayerPosition {
+                world,
+                level: level_state.current_level,
+                position,
+            });
+        }
+    }
+}
+
+/// Moves the respawned player back to where they were. Forgets the position once the reloaded
+/// world is gone or another level is shown, so a level reloaded without a player doesn't move the
+/// player of whatever spawns next.
+fn restore_player_position(
+    mut commands: Commands,
+    reloaded_player_position: Option<Res<ReloadedPlayerPosition>>,
+    level_state: Res<LevelState>,
+    world_query: Query<(), With<LevelSet>>,
+    mut player_query: Query<&mut Transform, Added<Player>>,
+) {
+    let reloaded_player_position = match reloaded_player_position {
+        Some(reloaded_player_position) => reloaded_player_position,
+        None => return,
+    };
+
+    if !world_query.contains(reloaded_player_position.world)
+        || reloaded_player_position.level != level_state.current_level
+    {
+        commands.remove_resource::<ReloadedPlayerPosition>();
+        return;
+    }
+
+    if let Ok(mut player_transform) = player_query.get_single_mut() {
+        let position = reloaded_player_position.position;
+        player_transform.translation = position.extend(player_transform.translation.z);
+        commands.remove_resource::<ReloadedPlayerPosition>();
+    }
+}
+
+/// Whether a wall has been drawn where the player is standing, in which case they go back to
+/// the level's spawn.
+fn is_wall(project: &LdtkJson, level: usize, position: Vec2) -> bool {
+    let layer = project
+        .levels
+        .get(level)
+        .and_then(|level| level.layer_instances.as_ref())
+        .and_then(|layers| layers.iter().find(|layer| layer.identifier == "Collisions"));
+
+    let layer = match layer {
+        Some(layer) => layer,
+        None => return false,
+    };
+
+    // The grid counts rows downwards from the top of the level, the world upwards
+    let cell = (position / GRID_SIZE).floor().as_ivec2();
+    let row = layer.c_hei - 1 - cell.y;
+    if cell.x < 0 || row < 0 || cell.x >= layer.c_wid || row >= layer.c_hei {
+        return true;
+    }
+
+    layer
+        .int_grid_csv
+        .get((row * layer.c_wid + cell.x) as usize)
+        == Some(&WALL_INT_CELL)
+}
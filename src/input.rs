@@ -0,0 +1,248 @@
+use std::collections::HashMap;
+
+use bevy::input::InputSystem;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::storage;
+
+const INPUT_BINDINGS_KEY: &str = "input_bindings";
+
+/// Everything the player can do, independent of the device used to do it.
+///
+/// Systems read these through `Res<Input<Action>>` exactly like they would read keys.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Action {
+    MoveUp,
+    MoveDown,
+    MoveLeft,
+    MoveRight,
+    Bork,
+    Confirm,
+    ToggleZoom,
+    Pause,
+    Drink,
+    ToggleFullscreen,
+}
+
+impl Action {
+    pub const ALL: [Action; 10] = [
+        Action::MoveUp,
+        Action::MoveDown,
+        Action::MoveLeft,
+        Action::MoveRight,
+        Action::Bork,
+        Action::Confirm,
+        Action::ToggleZoom,
+        Action::Pause,
+        Action::Drink,
+        // Replays store actions by their position here, new ones go at the end
+        Action::ToggleFullscreen,
+    ];
+}
+
+/// Which keys and gamepad buttons trigger each action. Stored in the `input_bindings` config so
+/// players can rebind them.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct InputBindings {
+    pub keys: HashMap<Action, Vec<KeyCode>>,
+    pub gamepad_buttons: HashMap<Action, Vec<GamepadButtonType>>,
+    pub move_stick_x: GamepadAxisType,
+    pub move_stick_y: GamepadAxisType,
+    /// Stick deflection below this is ignored
+    pub stick_dead_zone: f32,
+}
+
+impl Default for InputBindings {
+    fn default() -> Self {
+        let keys = HashMap::from([
+            (Action::MoveUp, vec![KeyCode::W, KeyCode::Up]),
+            (Action::MoveDown, vec![KeyCode::S, KeyCode::Down]),
+            (Action::MoveLeft, vec![KeyCode::A, KeyCode::Left]),
+            (Action::MoveRight, vec![KeyCode::D, KeyCode::Right]),
+            (Action::Bork, vec![KeyCode::Space]),
+            (Action::Confirm, vec![KeyCode::Return, KeyCode::Space]),
+            (Action::ToggleZoom, vec![KeyCode::M]),
+            (Action::ToggleFullscreen, vec![KeyCode::F11]),
+            (Action::Pause, vec![KeyCode::Escape, KeyCode::P]),
+            (Action::Drink, vec![KeyCode::E]),
+        ]);
+
+        let gamepad_buttons = HashMap::from([
+            (Action::MoveUp, vec![GamepadButtonType::DPadUp]),
+            (Action::MoveDown, vec![GamepadButtonType::DPadDown]),
+            (Action::MoveLeft, vec![GamepadButtonType::DPadLeft]),
+            (Action::MoveRight, vec![GamepadButtonType::DPadRight]),
+            (Action::Bork, vec![GamepadButtonType::South]),
+            (
+                Action::Confirm,
+                vec![GamepadButtonType::South, GamepadButtonType::Start],
+            ),
+            (Action::ToggleZoom, vec![GamepadButtonType::North]),
+            (Action::Pause, vec![GamepadButtonType::Start]),
+            (Action::Drink, vec![GamepadButtonType::West]),
+        ]);
+
+        Self {
+            keys,
+            gamepad_buttons,
+            move_stick_x: GamepadAxisType::LeftStickX,
+            move_stick_y: GamepadAxisType::LeftStickY,
+            stick_dead_zone: 0.2,
+        }
+    }
+}
+
+impl InputBindings {
+    /// Loads the saved bindings, writing out the defaults the first time so they can be edited.
+    pub fn load() -> Self {
+        let mut bindings: Self = match storage::load(INPUT_BINDINGS_KEY) {
+            Some(bindings) => bindings,
+            None => {
+                let bindings = Self::default();
+                bindings.save();
+                return bindings;
+            }
+        };
+
+        // Actions added after the bindings were saved start out with their defaults
+        let defaults = Self::default();
+        for action in Action::ALL {
+            bindings
+                .keys
+                .entry(action)
+                .or_insert_with(|| defaults.keys(action).to_vec());
+            bindings
+                .gamepad_buttons
+                .entry(action)
+                .or_insert_with(|| defaults.gamepad_buttons(action).to_vec());
+        }
+
+        bindings
+    }
+
+    pub fn save(&self) {
+        storage::save(INPUT_BINDINGS_KEY, self);
+    }
+
+    fn keys(&self, action: Action) -> &[KeyCode] {
+        self.keys.get(&action).map(Vec::as_slice).unwrap_or(&[])
+    }
+
+    fn gamepad_buttons(&self, action: Action) -> &[GamepadButtonType] {
+        self.gamepad_buttons
+            .get(&action)
+            .map(Vec::as_slice)
+            .unwrap_or(&[])
+    }
+
+    /// What to call the first key or button bound to `action` when telling the player to press
+    /// it. Face buttons go by their Xbox names.
+    pub fn button_name(&self, action: Action, gamepad: bool) -> String {
+        let name = if gamepad {
+            self.gamepad_buttons(action)
+                .first()
+                .map(|button| match button {
+                    GamepadButtonType::South => "A".to_string(),
+                    GamepadButtonType::East => "B".to_string(),
+                    GamepadButtonType::West => "X".to_string(),
+                    GamepadButtonType::North => "Y".to_string(),
+                    button => format!("{:?}", button),
+                })
+        } else {
+            self.keys(action).first().map(|key| format!("{:?}", key))
+        };
+
+        name.unwrap_or_else(|| format!("{:?}", action))
+    }
+}
+
+/// The direction the player wants to move in. Its length is at most 1, and is below 1 when a
+/// stick is only partly tilted.
+#[derive(Clone, Debug, Default)]
+pub struct MovementInput(pub Vec2);
+
+pub struct ActionInputPlugin;
+
+impl Plugin for ActionInputPlugin {
+    fn build(&self, app: &mut App) {
+        // Headless games insert their own bindings beforehand
+        if !app.world.contains_resource::<InputBindings>() {
+            app.insert_resource(InputBindings::load());
+        }
+
+        app.init_resource::<Input<Action>>()
+            .init_resource::<MovementInput>()
+            .add_system_to_stage(
+                CoreStage::PreUpdate,
+                update_actions.label("update_actions").after(InputSystem),
+            );
+    }
+}
+
+fn update_actions(
+    bindings: Res<InputBindings>,
+    keyboard_input: Res<Input<KeyCode>>,
+    gamepads: Res<Gamepads>,
+    gamepad_buttons: Res<Input<GamepadButton>>,
+    gamepad_axes: Res<Axis<GamepadAxis>>,
+    mut actions: ResMut<Input<Action>>,
+    mut movement: ResMut<MovementInput>,
+) {
+    actions.clear();
+
+    for action in Action::ALL {
+        let active = bindings
+            .keys(action)
+            .iter()
+            .any(|key| keyboard_input.pressed(*key))
+            || gamepads.iter().any(|gamepad| {
+                bindings
+                    .gamepad_buttons(action)
+                    .iter()
+                    .any(|button| gamepad_buttons.pressed(GamepadButton(*gamepad, *button)))
+            });
+
+        if active && !actions.pressed(action) {
+            actions.press(action);
+        } else if !active && actions.pressed(action) {
+            actions.release(action);
+        }
+    }
+
+    let mut direction = Vec2::default();
+    if actions.pressed(Action::MoveUp) {
+        direction.y = 1.;
+    } else if actions.pressed(Action::MoveDown) {
+        direction.y = -1.;
+    }
+
+    if actions.pressed(Action::MoveRight) {
+        direction.x = 1.;
+    } else if actions.pressed(Action::MoveLeft) {
+        direction.x = -1.;
+    }
+
+    // Digital input always moves at full speed, the stick only when nothing digital is held
+    if direction != Vec2::ZERO {
+        movement.0 = direction.normalize_or_zero();
+        return;
+    }
+
+    movement.0 = gamepads
+        .iter()
+        .map(|gamepad| {
+            Vec2::new(
+                gamepad_axes
+                    .get(GamepadAxis(*gamepad, bindings.move_stick_x))
+                    .unwrap_or(0.),
+                gamepad_axes
+                    .get(GamepadAxis(*gamepad, bindings.move_stick_y))
+                    .unwrap_or(0.),
+            )
+        })
+        .find(|stick| stick.length() > bindings.stick_dead_zone)
+        .map(|stick| stick.clamp_length_max(1.))
+        .unwrap_or_default();
+}
@@ -0,0 +1,158 @@
+use std::cmp::Ordering;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    bitmap_text::{BitmapText, BitmapTextBundle, HEADING_SCALE},
+    input::Action,
+    storage,
+    types::GameState,
+};
+
+const LEADERBOARD_KEY: &str = "leaderboard";
+
+pub const LEADERBOARD_SIZE: usize = 10;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct LeaderboardEntry {
+    pub name: String,
+    pub coins: u32,
+    pub play_time: f64,
+}
+
+impl LeaderboardEntry {
+    /// More coins rank higher, and the faster run wins a tie.
+    fn rank(&self, other: &Self) -> Ordering {
+        other.coins.cmp(&self.coins).then(
+            self.play_time
+                .partial_cmp(&other.play_time)
+                .unwrap_or(Ordering::Equal),
+        )
+    }
+}
+
+/// The best runs played on this machine, best first.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Leaderboard {
+    pub entries: Vec<LeaderboardEntry>,
+}
+
+impl Leaderboard {
+    pub fn load() -> Self {
+        storage::load(LEADERBOARD_KEY).unwrap_or_default()
+    }
+
+    pub fn save(&self) {
+        storage::save(LEADERBOARD_KEY, self);
+    }
+
+    /// Whether a run with these results would make it onto the board.
+    pub fn qualifies(&self, coins: u32, play_time: f64) -> bool {
+        let candidate = LeaderboardEntry {
+            name: String::new(),
+            coins,
+            play_time,
+        };
+
+        self.entries.len() < LEADERBOARD_SIZE
+            || self
+                .entries
+                .last()
+                .map(|last| candidate.rank(last) == Ordering::Less)
+                .unwrap_or(true)
+    }
+
+    pub fn insert(&mut self, entry: LeaderboardEntry) {
+        let index = self
+            .entries
+            .iter()
+            .position(|existing| entry.rank(existing) == Ordering::Less)
+            .unwrap_or(self.entries.len());
+
+        self.entries.insert(index, entry);
+        self.entries.truncate(LEADERBOARD_SIZE);
+    }
+}
+
+#[derive(Component, Default, Clone, Debug)]
+pub struct OnlyInLeaderboard;
+
+pub struct LeaderboardPlugin;
+
+impl Plugin for LeaderboardPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(SystemSet::on_enter(GameState::Leaderboard).with_system(spawn_ui))
+            .add_system_set(SystemSet::on_update(GameState::Leaderboard).with_system(go_back))
+            .add_system_set(SystemSet::on_exit(GameState::Leaderboard).with_system(cleanup));
+    }
+}
+
+fn spawn_ui(mut commands: Commands) {
+    let leaderboard = Leaderboard::load();
+
+    let mut lines: Vec<String> = leaderboard
+        .entries
+        .iter()
+        .enumerate()
+        .map(|(index, entry)| {
+            format!(
+                "{:>2}. {:<12} {:>4} coins {:>5}s",
+                index + 1,
+                entry.name,
+                entry.coins,
+                entry.play_time as u32
+            )
+        })
+        .collect();
+
+    if lines.is_empty() {
+        lines.push("No runs yet".to_string());
+    }
+
+    // A space keeps the height of an empty line
+    lines.push(" ".to_string());
+    lines.push("Press Enter to go back".to_string());
+
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                position_type: PositionType::Absolute,
+                flex_direction: FlexDirection::ColumnReverse,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..Default::default()
+            },
+            color: Color::rgb(0.098, 0.078, 0.169).into(),
+            ..Default::default()
+        })
+        .insert(OnlyInLeaderboard)
+        .with_children(|parent| {
+            parent.spawn_bundle(BitmapTextBundle::new(
+                BitmapText::new("High Scores")
+                    .with_color(Color::WHITE)
+                    .with_scale(HEADING_SCALE),
+            ));
+
+            for line in lines {
+                parent.spawn_bundle(BitmapTextBundle::new(
+                    BitmapText::new(line).with_color(Color::WHITE),
+                ));
+            }
+        });
+}
+
+fn go_back(mut actions: ResMut<Input<Action>>, mut game_state: ResMut<State<GameState>>) {
+    if actions.just_released(Action::Confirm) || actions.just_released(Action::Pause) {
+        // The main menu resumes this frame, so don't let it start a game with the same press
+        actions.clear_just_released(Action::Confirm);
+        game_state.pop().unwrap();
+    }
+}
+
+fn cleanup(mut commands: Commands, query: Query<Entity, With<OnlyInLeaderboard>>) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
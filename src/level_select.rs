@@ -0,0 +1,312 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    bitmap_text::{BitmapText, BitmapTextBundle, HEADING_SCALE},
+    endless::EndlessRun,
+    game::game::{GameWorldState, LevelStartState},
+    input::Action,
+    levels::{IncrementLevel, LevelState},
+    save::{self, SaveGame},
+    storage,
+    types::GameState,
+};
+
+const LEVEL_RECORDS_KEY: &str = "level_records";
+
+const SELECTED_COLOR: Color = Color::WHITE;
+
+const UNSELECTED_COLOR: Color = Color::rgb(0.5, 0.5, 0.5);
+
+/// The best results of every run that cleared a level.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LevelRecord {
+    pub best_coins: Option<u32>,
+    /// Seconds between arriving on the level and taking its stairs
+    pub fastest_time: Option<f64>,
+}
+
+/// Which levels have been reached and the best result on each, indexed like `levels.ldtk`.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LevelRecords {
+    pub levels: Vec<LevelRecord>,
+    /// How many levels, counting from the first, can be picked from the level select
+    pub unlocked: usize,
+}
+
+impl LevelRecords {
+    pub fn load() -> Self {
+        storage::load(LEVEL_RECORDS_KEY).unwrap_or_default()
+    }
+
+    pub fn save(&self) {
+        storage::save(LEVEL_RECORDS_KEY, self);
+    }
+
+    /// The first level is always unlocked.
+    pub fn is_unlocked(&self, level: usize) -> bool {
+        level < self.unlocked.max(1)
+    }
+
+    pub fn get(&self, level: usize) -> Option<&LevelRecord> {
+        self.levels.get(level)
+    }
+
+    /// Returns whether the level was newly unlocked.
+    pub fn unlock(&mut self, level: usize) -> bool {
+        if self.is_unlocked(level) {
+            return false;
+        }
+
+        self.unlocked = level + 1;
+        true
+    }
+
+    pub fn record(&mut self, level: usize, coins: u32, time: f64) {
+        if self.levels.len() <= level {
+            self.levels.resize(level + 1, LevelRecord::default());
+        }
+
+        let record = &mut self.levels[level];
+        record.best_coins = Some(record.best_coins.map_or(coins, |best| best.max(coins)));
+        record.fastest_time = Some(record.fastest_time.map_or(time, |best| best.min(time)));
+    }
+}
+
+/// The level passed with `--level`, started as soon as the main menu is up.
+#[derive(Clone, Debug)]
+pub struct StartAtLevel(pub String);
+
+#[derive(Component, Default, Clone, Debug)]
+pub struct OnlyInLevelSelect;
+
+#[derive(Component, Clone, Debug)]
+pub struct LevelSelectEntry(usize);
+
+/// Index of the highlighted level.
+#[derive(Default, Clone, Debug)]
+pub struct LevelSelectSelection(usize);
+
+pub struct LevelSelectPlugin;
+
+impl Plugin for LevelSelectPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(LevelRecords::load())
+            .init_resource::<LevelSelectSelection>()
+            .add_system_set(
+                SystemSet::on_update(GameState::MainMenu).with_system(start_at_level_argument),
+            )
+            .add_system_set(
+                SystemSet::on_update(GameState::InGame)
+                    .with_system(unlock_reached_level.after("change_level"))
+                    .with_system(record_level_result.before("change_level")),
+            )
+            .add_system_set(
+                SystemSet::on_enter(GameState::LevelSelect)
+                    .with_system(reset_selection)
+                    .with_system(spawn_ui),
+            )
+            .add_system_set(
+                SystemSet::on_update(GameState::LevelSelect)
+                    .with_system(navigate_menu.label("navigate_level_select"))
+                    .with_system(highlight_selection.after("navigate_level_select"))
+                    .with_system(select_level.after("navigate_level_select")),
+            )
+            .add_system_set(SystemSet::on_exit(GameState::LevelSelect).with_system(cleanup));
+    }
+}
+
+/// Starts a fresh run on the given level instead of the first one.
+fn start_run_at(commands: &mut Commands, level: usize) {
+    save::continue_run(
+        commands,
+        SaveGame {
+            version: save::SAVE_VERSION,
+            current_level: level,
+            world: GameWorldState::default(),
+            endless: None,
+        },
+    );
+}
+
+fn start_at_level_argument(
+    mut commands: Commands,
+    start_at_level: Option<Res<StartAtLevel>>,
+    level_state: Res<LevelState>,
+    mut game_state: ResMut<State<GameState>>,
+) {
+    let start_at_level = match start_at_level {
+        Some(start_at_level) => start_at_level,
+        None => return,
+    };
+
+    commands.remove_resource::<StartAtLevel>();
+
+    match level_state.find_level(&start_at_level.0) {
+        Some(level) => {
+            start_run_at(&mut commands, level);
+            game_state.set(GameState::InGame).unwrap();
+        }
+        None => error!(
+            "No level {}, expected a number from 1 to {} or one of {:?}",
+            start_at_level.0, level_state.max_levels, level_state.level_names
+        ),
+    }
+}
+
+/// Endless runs have no fixed levels to unlock or keep records for.
+fn unlock_reached_level(
+    level_state: Res<LevelState>,
+    endless_run: Option<Res<EndlessRun>>,
+    mut level_records: ResMut<LevelRecords>,
+) {
+    if endless_run.is_none()
+        && level_state.is_changed()
+        && level_records.unlock(level_state.current_level)
+    {
+        level_records.save();
+    }
+}
+
+/// Runs before the level changes so the results are stored for the level that was just cleared.
+fn record_level_result(
+    mut increment_level_event: EventReader<IncrementLevel>,
+    level_state: Res<LevelState>,
+    game_world_state: Res<GameWorldState>,
+    level_start_state: Res<LevelStartState>,
+    endless_run: Option<Res<EndlessRun>>,
+    mut level_records: ResMut<LevelRecords>,
+) {
+    if increment_level_event.iter().count() == 0 || endless_run.is_some() {
+        return;
+    }
+
+    level_records.record(
+        level_state.current_level,
+        game_world_state
+            .coins
+            .saturating_sub(level_start_state.0.coins),
+        game_world_state.play_time - level_start_state.0.play_time,
+    );
+    level_records.save();
+}
+
+fn reset_selection(mut selection: ResMut<LevelSelectSelection>) {
+    selection.0 = 0;
+}
+
+fn spawn_ui(
+    mut commands: Commands,
+    level_state: Res<LevelState>,
+    level_records: Res<LevelRecords>,
+) {
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                position_type: PositionType::Absolute,
+                flex_direction: FlexDirection::ColumnReverse,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..Default::default()
+            },
+            color: Color::rgb(0.098, 0.078, 0.169).into(),
+            ..Default::default()
+        })
+        .insert(OnlyInLevelSelect)
+        .with_children(|parent| {
+            parent.spawn_bundle(BitmapTextBundle::new(
+                BitmapText::new("Select Level")
+                    .with_color(Color::WHITE)
+                    .with_scale(HEADING_SCALE),
+            ));
+
+            for (index, name) in level_state.level_names.iter().enumerate() {
+                let label = if !level_records.is_unlocked(index) {
+                    format!("{:>2}. {:<16} {:>16}", index + 1, "???", "locked")
+                } else {
+                    let results = match level_records.get(index) {
+                        Some(LevelRecord {
+                            best_coins: Some(coins),
+                            fastest_time: Some(time),
+                        }) => format!("{:>4} coins {:>5}s", coins, *time as u32),
+                        _ => "not cleared".to_string(),
+                    };
+                    format!("{:>2}. {:<16} {:>16}", index + 1, name, results)
+                };
+
+                parent
+                    .spawn_bundle(BitmapTextBundle::new(
+                        BitmapText::new(label).with_color(UNSELECTED_COLOR),
+                    ))
+                    .insert(LevelSelectEntry(index));
+            }
+        });
+}
+
+/// Only unlocked levels can be highlighted.
+fn navigate_menu(
+    actions: Res<Input<Action>>,
+    level_state: Res<LevelState>,
+    level_records: Res<LevelRecords>,
+    mut selection: ResMut<LevelSelectSelection>,
+) {
+    let item_count = (0..level_state.max_levels)
+        .take_while(|level| level_records.is_unlocked(*level))
+        .count()
+        .max(1);
+
+    if actions.just_pressed(Action::MoveDown) {
+        selection.0 = (selection.0 + 1) % item_count;
+    } else if actions.just_pressed(Action::MoveUp) {
+        selection.0 = (selection.0 + item_count - 1) % item_count;
+    }
+}
+
+fn highlight_selection(
+    selection: Res<LevelSelectSelection>,
+    mut query: Query<(&LevelSelectEntry, &mut BitmapText)>,
+) {
+    for (entry, mut text) in query.iter_mut() {
+        let color = if entry.0 == selection.0 {
+            SELECTED_COLOR
+        } else {
+            UNSELECTED_COLOR
+        };
+        if text.color != color {
+            text.color = color;
+        }
+    }
+}
+
+fn select_level(
+    mut commands: Commands,
+    mut actions: ResMut<Input<Action>>,
+    selection: Res<LevelSelectSelection>,
+    level_state: Res<LevelState>,
+    mut game_state: ResMut<State<GameState>>,
+) {
+    if actions.just_pressed(Action::Pause) {
+        actions.clear_just_pressed(Action::Pause);
+        game_state.pop().unwrap();
+        return;
+    }
+
+    if !actions.just_released(Action::Confirm) || selection.0 >= level_state.max_levels {
+        return;
+    }
+
+    // Whichever state comes next starts running this frame, so don't let it see the same press
+    actions.clear_just_released(Action::Confirm);
+
+    start_run_at(&mut commands, selection.0);
+    game_state.replace(GameState::InGame).unwrap();
+}
+
+fn cleanup(mut commands: Commands, query: Query<Entity, With<OnlyInLevelSelect>>) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
@@ -1,6 +1,11 @@
 use bevy::prelude::*;
+use bevy_ecs_ldtk::ldtk::{LdtkJson, Level};
 use bevy_ecs_ldtk::prelude::*;
 
+use crate::endless::EndlessRun;
+use crate::game::components::TimeToLive;
+use crate::game::level::validation::validate_loaded_levels;
+use crate::game::pickup::Pickup;
 use crate::types::{GameState, LevelAssets};
 
 #[derive(Default, Clone, Debug)]
@@ -9,9 +14,49 @@ pub struct ResetLevel;
 #[derive(Default, Clone, Debug)]
 pub struct IncrementLevel;
 
+/// Spawns the current level again from scratch.
+#[derive(Default, Clone, Debug)]
+pub struct RestartLevel;
+
+/// Jumps straight to the level with the given index.
+#[derive(Default, Clone, Debug)]
+pub struct LoadLevel(pub usize);
+
+#[derive(Default, Clone, Debug)]
 pub struct LevelState {
     pub current_level: usize,
+    /// How many levels `levels.ldtk` contains, known once it has loaded
     pub max_levels: usize,
+    /// Shown when a level starts, from each level's `name` field or else its identifier
+    pub level_names: Vec<String>,
+    /// The LDtk identifier of each level, such as `Level_0`
+    pub level_identifiers: Vec<String>,
+}
+
+impl LevelState {
+    pub fn current_level_name(&self) -> &str {
+        self.level_names
+            .get(self.current_level)
+            .map(String::as_str)
+            .unwrap_or_default()
+    }
+
+    /// Finds a level by its number, identifier or name, ignoring case. Numbers start at 1 like
+    /// the level select and the card shown when a level starts.
+    pub fn find_level(&self, level: &str) -> Option<usize> {
+        if let Ok(number) = level.parse::<usize>() {
+            return number
+                .checked_sub(1)
+                .filter(|index| *index < self.max_levels);
+        }
+
+        self.level_identifiers
+            .iter()
+            .zip(&self.level_names)
+            .position(|(identifier, name)| {
+                identifier.eq_ignore_ascii_case(level) || name.eq_ignore_ascii_case(level)
+            })
+    }
 }
 
 pub struct LevelsPlugin;
@@ -19,26 +64,76 @@ pub struct LevelsPlugin;
 impl Plugin for LevelsPlugin {
     fn build(&self, app: &mut App) {
         app.insert_resource(LevelSelection::Index(0))
-            .insert_resource(LevelState {
-                current_level: 0,
-                max_levels: 3,
-            })
+            .init_resource::<LevelState>()
             .add_event::<IncrementLevel>()
             .add_event::<ResetLevel>()
+            .add_event::<LoadLevel>()
+            .add_event::<RestartLevel>()
             .add_plugin(LdtkPlugin)
+            .add_system_set(
+                SystemSet::on_exit(GameState::Loading)
+                    .with_system(count_levels)
+                    .with_system(validate_loaded_levels),
+            )
             .add_system_set(
                 SystemSet::on_enter(GameState::InGame)
                     .with_system(setup.label("level_setup"))
-                    .with_system(reset_level.after("level_setup")),
+                    .with_system(reset_level.after("level_setup"))
+                    .with_system(load_level.after("level_setup")),
             )
             .add_system_set(SystemSet::on_update(GameState::InGame))
             .add_system_set(SystemSet::on_exit(GameState::InGame).with_system(cleanup))
             .add_system(reset_level)
-            .add_system(change_level);
+            .add_system(load_level)
+            .add_system(restart_level)
+            .add_system(change_level.label("change_level"));
+    }
+}
+
+fn count_levels(
+    level_assets: Res<LevelAssets>,
+    ldtk_assets: Res<Assets<LdtkAsset>>,
+    mut level_state: ResMut<LevelState>,
+) {
+    if let Some(ldtk_asset) = ldtk_assets.get(&level_assets.levels) {
+        read_level_list(&mut level_state, &ldtk_asset.project);
     }
 }
 
-fn setup(mut commands: Commands, level_assets: Res<LevelAssets>) {
+/// Fills in the names and number of levels from the project.
+pub fn read_level_list(level_state: &mut LevelState, project: &LdtkJson) {
+    let levels = &project.levels;
+    level_state.level_names = levels.iter().map(level_name).collect();
+    level_state.level_identifiers = levels
+        .iter()
+        .map(|level| level.identifier.clone())
+        .collect();
+    level_state.max_levels = level_state.level_names.len();
+}
+
+/// The level's `name` field, falling back to its identifier.
+pub fn level_name(level: &Level) -> String {
+    level
+        .field_instances
+        .iter()
+        .find(|field| field.identifier == "name")
+        .and_then(|field| match &field.value {
+            FieldValue::String(name) => name.clone(),
+            _ => None,
+        })
+        .unwrap_or_else(|| level.identifier.clone())
+}
+
+/// Endless runs spawn their own floors instead.
+fn setup(
+    mut commands: Commands,
+    level_assets: Res<LevelAssets>,
+    endless_run: Option<Res<EndlessRun>>,
+) {
+    if endless_run.is_some() {
+        return;
+    }
+
     commands.spawn_bundle(LdtkWorldBundle {
         ldtk_handle: level_assets.levels.clone(),
         ..Default::default()
@@ -57,18 +152,71 @@ fn reset_level(
     }
 }
 
+fn load_level(
+    mut level_selection: ResMut<LevelSelection>,
+    mut level_state: ResMut<LevelState>,
+    mut load_level_event: EventReader<LoadLevel>,
+    endless_run: Option<Res<EndlessRun>>,
+) {
+    for LoadLevel(level) in load_level_event.iter() {
+        let next_level = match endless_run {
+            // Endless runs go as deep as the player can make it
+            Some(_) => *level,
+            None => (*level).min(level_state.max_levels.saturating_sub(1)),
+        };
+        level_state.current_level = next_level;
+        *level_selection = LevelSelection::Index(next_level);
+    }
+}
+
 fn change_level(
     mut level_selection: ResMut<LevelSelection>,
     mut level_state: ResMut<LevelState>,
     mut increment_level_event: EventReader<IncrementLevel>,
+    endless_run: Option<Res<EndlessRun>>,
+    mut game_state: ResMut<State<GameState>>,
 ) {
     for _ in increment_level_event.iter() {
-        let next_level = (level_state.current_level + 1) % level_state.max_levels;
+        let next_level = level_state.current_level + 1;
+        if endless_run.is_none() && next_level >= level_state.max_levels {
+            // Taking the stairs on the last level wins the run
+            game_state.set(GameState::Victory).ok();
+            return;
+        }
+
         level_state.current_level = next_level;
         *level_selection = LevelSelection::Index(next_level);
     }
 }
 
+fn restart_level(
+    mut commands: Commands,
+    level_assets: Res<LevelAssets>,
+    mut restart_level_event: EventReader<RestartLevel>,
+    world_query: Query<Entity, With<LevelSet>>,
+    // Projectiles and dropped coins aren't part of the level hierarchy
+    stray_query: Query<Entity, (Or<(With<TimeToLive>, With<Pickup>)>, Without<Parent>)>,
+    endless_run: Option<Res<EndlessRun>>,
+) {
+    if restart_level_event.iter().count() == 0 {
+        return;
+    }
+
+    for entity in world_query.iter().chain(stray_query.iter()) {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    // The endless plugin generates the floor again by itself
+    if endless_run.is_some() {
+        return;
+    }
+
+    commands.spawn_bundle(LdtkWorldBundle {
+        ldtk_handle: level_assets.levels.clone(),
+        ..Default::default()
+    });
+}
+
 fn cleanup(mut commands: Commands, world_query: Query<Entity, With<LevelSet>>) {
     if let Ok(ldtk_world_entity) = world_query.get_single() {
         commands.entity(ldtk_world_entity).despawn_recursive();
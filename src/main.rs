@@ -1,58 +1,143 @@
+use bevy::audio::AudioSink;
 use bevy::prelude::*;
 use big_brain::BigBrainPlugin;
 use heron::prelude::*;
 
 use bevy_asset_loader::AssetLoader;
 use bevy_embedded_assets::EmbeddedAssetPlugin;
-use types::{AudioAssets, FontAssets, ImageAssets, LevelAssets};
+use types::{AudioAssets, EnemyAssets, FontAssets, ImageAssets, LevelAssets};
 
+mod bitmap_text;
 mod camera;
+mod cli;
+mod credits;
+mod endless;
 mod game;
 mod game_over;
+mod headless;
+#[cfg(feature = "dev")]
+mod hot_reload;
+mod input;
+mod leaderboard;
+mod level_select;
 mod levels;
 mod main_menu;
+mod pause;
+mod pixel_perfect;
+mod replay;
+mod save;
+mod settings;
+mod storage;
 mod texture;
 mod types;
 
 fn main() {
+    let options = cli::CliOptions::from_args();
+
+    if options.validate_levels {
+        std::process::exit(game::level::validation::run_cli());
+    }
+
+    if let Some(frames) = options.headless_frames {
+        headless::run(frames, &options.headless_keys);
+        return;
+    }
+
     let mut app = App::new();
 
-    // Load all assets before the game starts up
-    AssetLoader::new(types::GameState::Loading)
-        .continue_to_state(types::GameState::MainMenu)
-        .with_collection::<AudioAssets>()
-        .with_collection::<ImageAssets>()
-        .with_collection::<FontAssets>()
-        .with_collection::<LevelAssets>()
-        .build(&mut app);
+    // Read assets from disk and reload them when they change
+    #[cfg(feature = "dev")]
+    app.insert_resource(bevy::asset::AssetServerSettings {
+        watch_for_changes: true,
+        ..Default::default()
+    });
 
     app.insert_resource(WindowDescriptor {
         title: "Obscure Resurrection".to_string(),
+        width: pixel_perfect::VIEW_WIDTH as f32 * camera::WINDOW_SCALE,
+        height: pixel_perfect::VIEW_HEIGHT as f32 * camera::WINDOW_SCALE,
         ..Default::default()
     })
-    .insert_resource(ClearColor(Color::rgb(0.098, 0.078, 0.169)))
-    .add_state(types::GameState::Loading)
     // Embed assets into the binary
     .add_plugins_with(DefaultPlugins, |group| {
-        group.add_before::<bevy::asset::AssetPlugin, _>(EmbeddedAssetPlugin)
+        if cfg!(feature = "dev") {
+            group
+        } else {
+            group.add_before::<bevy::asset::AssetPlugin, _>(EmbeddedAssetPlugin)
+        }
     })
-    .add_plugin(PhysicsPlugin::default())
-    .add_plugin(BigBrainPlugin)
-    .add_plugin(texture::plugin::TexturePlugin)
-    .add_plugin(camera::CameraPlugin)
-    .add_plugin(main_menu::MainMenuPlugin)
-    .add_plugin(game_over::GameOverPlugin)
-    .add_plugin(levels::LevelsPlugin)
-    .add_plugin(game::game::GamePlugin)
-    .add_system_set(
-        SystemSet::on_enter(types::GameState::MainMenu).with_system(play_background_audio),
-    )
-    .run();
+    // Before the game so the fixed step replaces the default timing
+    .add_plugin(replay::ReplayPlugin {
+        record: options.record,
+        replay: options.replay,
+    });
+
+    if let Some(level) = options.level {
+        app.insert_resource(level_select::StartAtLevel(level));
+    }
+
+    if let Some(seed) = options.seed {
+        app.insert_resource(endless::StartEndlessRun(seed));
+    }
+
+    build_game(&mut app);
+
+    app.run();
 }
 
-fn play_background_audio(audio_assets: Res<AudioAssets>, audio: Res<Audio>) {
-    audio.play_with_settings(
+/// Adds the game itself to an app that already has Bevy's default plugins.
+pub fn build_game(app: &mut App) {
+    // Load all assets before the game starts up
+    AssetLoader::new(types::GameState::Loading)
+        .continue_to_state(types::GameState::MainMenu)
+        .with_collection::<AudioAssets>()
+        .with_collection::<ImageAssets>()
+        .with_collection::<FontAssets>()
+        .with_collection::<LevelAssets>()
+        .with_collection::<EnemyAssets>()
+        .build(app);
+
+    app.insert_resource(ClearColor(Color::rgb(0.098, 0.078, 0.169)))
+        .add_state(types::GameState::Loading)
+        .add_plugin(PhysicsPlugin::default())
+        .add_plugin(input::ActionInputPlugin)
+        .add_plugin(BigBrainPlugin)
+        .add_plugin(texture::plugin::TexturePlugin)
+        .add_plugin(camera::CameraPlugin)
+        .add_plugin(bitmap_text::BitmapTextPlugin)
+        .add_plugin(pixel_perfect::PixelPerfectPlugin)
+        .add_plugin(main_menu::MainMenuPlugin)
+        .add_plugin(game_over::GameOverPlugin)
+        .add_plugin(leaderboard::LeaderboardPlugin)
+        .add_plugin(level_select::LevelSelectPlugin)
+        .add_plugin(endless::EndlessPlugin)
+        .add_plugin(pause::PausePlugin)
+        .add_plugin(settings::SettingsPlugin)
+        .add_plugin(credits::CreditsPlugin)
+        .add_plugin(levels::LevelsPlugin)
+        .add_plugin(save::SavePlugin)
+        .add_plugin(game::game::GamePlugin)
+        // Start the music once, menus can be entered many times
+        .add_system_set(
+            SystemSet::on_exit(types::GameState::Loading).with_system(play_background_audio),
+        );
+
+    #[cfg(feature = "dev")]
+    app.add_plugin(hot_reload::HotReloadPlugin);
+}
+
+fn play_background_audio(
+    mut commands: Commands,
+    audio_assets: Res<AudioAssets>,
+    audio: Res<Audio>,
+    audio_sinks: Res<Assets<AudioSink>>,
+    settings: Res<settings::Settings>,
+) {
+    let sink = audio.play_with_settings(
         audio_assets.background_music.clone(),
-        PlaybackSettings::LOOP,
+        PlaybackSettings::LOOP.with_volume(settings.music_volume()),
     );
+
+    // A strong handle keeps the sink around to change the volume later
+    commands.insert_resource(settings::BackgroundMusic(audio_sinks.get_handle(sink)));
 }
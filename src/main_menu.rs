@@ -1,22 +1,111 @@
+use bevy::app::AppExit;
 use bevy::prelude::*;
 
+use crate::bitmap_text::{BitmapText, BitmapTextBundle};
+use crate::endless;
+use crate::game::sfx;
+use crate::input::Action;
+use crate::save;
+use crate::settings::Settings;
 use crate::types::GameState;
-use crate::types::ImageAssets;
+use crate::types::{AudioAssets, ImageAssets};
+
+const SELECTED_COLOR: Color = Color::WHITE;
+
+const UNSELECTED_COLOR: Color = Color::rgb(0.5, 0.5, 0.5);
+
+const HOVERED_BACKGROUND: Color = Color::rgba(1., 1., 1., 0.1);
 
 #[derive(Component, Default, Clone, Debug)]
 pub struct OnlyInMainMenu;
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MainMenuItem {
+    Continue,
+    NewGame,
+    EndlessMode,
+    LevelSelect,
+    HighScores,
+    Settings,
+    Credits,
+    Quit,
+}
+
+impl MainMenuItem {
+    const ALL: [MainMenuItem; 8] = [
+        MainMenuItem::Continue,
+        MainMenuItem::NewGame,
+        MainMenuItem::EndlessMode,
+        MainMenuItem::LevelSelect,
+        MainMenuItem::HighScores,
+        MainMenuItem::Settings,
+        MainMenuItem::Credits,
+        MainMenuItem::Quit,
+    ];
+
+    fn label(&self) -> &'static str {
+        match self {
+            MainMenuItem::Continue => "Continue",
+            MainMenuItem::NewGame => "New Game",
+            MainMenuItem::EndlessMode => "Endless Mode",
+            MainMenuItem::LevelSelect => "Level Select",
+            MainMenuItem::HighScores => "High Scores",
+            MainMenuItem::Settings => "Settings",
+            MainMenuItem::Credits => "Credits",
+            MainMenuItem::Quit => "Quit",
+        }
+    }
+
+    /// Continue needs a saved run, and a browser tab can't be quit.
+    fn is_available(&self) -> bool {
+        match self {
+            MainMenuItem::Continue => save::has_save(),
+            MainMenuItem::Quit => !cfg!(target_arch = "wasm32"),
+            _ => true,
+        }
+    }
+}
+
+/// Index into `MainMenuSelection::items`, which are picked when the menu is shown.
+#[derive(Component, Clone, Debug)]
+pub struct MainMenuEntry(usize);
+
+#[derive(Default, Clone, Debug)]
+pub struct MainMenuSelection {
+    items: Vec<MainMenuItem>,
+    selected: usize,
+}
+
 pub struct MainMenuPlugin;
 
 impl Plugin for MainMenuPlugin {
     fn build(&self, app: &mut App) {
-        app.add_system_set(SystemSet::on_enter(GameState::MainMenu).with_system(spawn_ui))
-            .add_system_set(SystemSet::on_update(GameState::MainMenu).with_system(load_game))
+        app.init_resource::<MainMenuSelection>()
+            .add_system_set(SystemSet::on_enter(GameState::MainMenu).with_system(spawn_ui))
+            .add_system_set(
+                SystemSet::on_update(GameState::MainMenu)
+                    .with_system(navigate_menu.label("navigate_main_menu"))
+                    .with_system(hover_entries.label("navigate_main_menu"))
+                    .with_system(highlight_selection.after("navigate_main_menu"))
+                    .with_system(select_menu_item.after("navigate_main_menu")),
+            )
             .add_system_set(SystemSet::on_exit(GameState::MainMenu).with_system(cleanup));
     }
 }
 
-fn spawn_ui(mut commands: Commands, image_assets: Res<ImageAssets>) {
+fn spawn_ui(
+    mut commands: Commands,
+    image_assets: Res<ImageAssets>,
+    mut selection: ResMut<MainMenuSelection>,
+) {
+    *selection = MainMenuSelection {
+        items: MainMenuItem::ALL
+            .into_iter()
+            .filter(MainMenuItem::is_available)
+            .collect(),
+        selected: 0,
+    };
+
     commands
         .spawn_bundle(NodeBundle {
             style: Style {
@@ -41,12 +130,177 @@ fn spawn_ui(mut commands: Commands, image_assets: Res<ImageAssets>) {
                 ..Default::default()
             });
         });
+
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: Rect {
+                    left: Val::Px(20.),
+                    bottom: Val::Px(20.),
+                    ..Default::default()
+                },
+                flex_direction: FlexDirection::ColumnReverse,
+                align_items: AlignItems::FlexStart,
+                ..Default::default()
+            },
+            color: Color::NONE.into(),
+            ..Default::default()
+        })
+        .insert(OnlyInMainMenu)
+        .with_children(|parent| {
+            for (index, item) in selection.items.iter().enumerate() {
+                parent
+                    .spawn_bundle(ButtonBundle {
+                        style: Style {
+                            padding: Rect {
+                                left: Val::Px(10.),
+                                right: Val::Px(10.),
+                                ..Default::default()
+                            },
+                            ..Default::default()
+                        },
+                        color: Color::NONE.into(),
+                        ..Default::default()
+                    })
+                    .insert(MainMenuEntry(index))
+                    .with_children(|parent| {
+                        parent.spawn_bundle(BitmapTextBundle::new(
+                            BitmapText::new(item.label()).with_color(UNSELECTED_COLOR),
+                        ));
+                    });
+            }
+        });
+}
+
+/// Keyboard and gamepad move through the entries, wrapping around at either end.
+fn navigate_menu(
+    actions: Res<Input<Action>>,
+    audio: Res<Audio>,
+    audio_assets: Res<AudioAssets>,
+    settings: Res<Settings>,
+    mut selection: ResMut<MainMenuSelection>,
+) {
+    let item_count = selection.items.len().max(1);
+
+    if actions.just_pressed(Action::MoveDown) {
+        selection.selected = (selection.selected + 1) % item_count;
+    } else if actions.just_pressed(Action::MoveUp) {
+        selection.selected = (selection.selected + item_count - 1) % item_count;
+    } else {
+        return;
+    }
+
+    sfx::play(&audio, &settings, &audio_assets.block);
 }
 
-fn load_game(keyboard_input: Res<Input<KeyCode>>, mut game_state: ResMut<State<GameState>>) {
-    if keyboard_input.just_released(KeyCode::Space) || keyboard_input.just_released(KeyCode::Return)
-    {
-        game_state.set(GameState::InGame).unwrap();
+/// The mouse selects whatever it moves onto.
+fn hover_entries(
+    audio: Res<Audio>,
+    audio_assets: Res<AudioAssets>,
+    settings: Res<Settings>,
+    mut selection: ResMut<MainMenuSelection>,
+    query: Query<(&Interaction, &MainMenuEntry), Changed<Interaction>>,
+) {
+    for (interaction, entry) in query.iter() {
+        if *interaction == Interaction::Hovered && selection.selected != entry.0 {
+            selection.selected = entry.0;
+            sfx::play(&audio, &settings, &audio_assets.block);
+        }
+    }
+}
+
+fn highlight_selection(
+    selection: Res<MainMenuSelection>,
+    mut button_query: Query<(&MainMenuEntry, &Interaction, &Children, &mut UiColor)>,
+    mut text_query: Query<&mut BitmapText>,
+) {
+    for (entry, interaction, children, mut color) in button_query.iter_mut() {
+        *color = if *interaction == Interaction::None {
+            Color::NONE.into()
+        } else {
+            HOVERED_BACKGROUND.into()
+        };
+
+        let text_color = if entry.0 == selection.selected {
+            SELECTED_COLOR
+        } else {
+            UNSELECTED_COLOR
+        };
+        for child in children.iter() {
+            if let Ok(mut text) = text_query.get_mut(*child) {
+                if text.color != text_color {
+                    text.color = text_color;
+                }
+            }
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn select_menu_item(
+    mut commands: Commands,
+    mut actions: ResMut<Input<Action>>,
+    selection: Res<MainMenuSelection>,
+    time: Res<Time>,
+    audio: Res<Audio>,
+    audio_assets: Res<AudioAssets>,
+    settings: Res<Settings>,
+    mut game_state: ResMut<State<GameState>>,
+    mut app_exit_events: EventWriter<AppExit>,
+    click_query: Query<(&Interaction, &MainMenuEntry), Changed<Interaction>>,
+) {
+    let clicked = click_query
+        .iter()
+        .find(|(interaction, _)| **interaction == Interaction::Clicked)
+        .map(|(_, entry)| entry.0);
+
+    let index = if actions.just_released(Action::Confirm) {
+        // Whichever state comes next starts running this frame, so don't let it see the same press
+        actions.clear_just_released(Action::Confirm);
+        selection.selected
+    } else if let Some(index) = clicked {
+        index
+    } else {
+        return;
+    };
+
+    let item = match selection.items.get(index) {
+        Some(item) => *item,
+        None => return,
+    };
+
+    sfx::play(&audio, &settings, &audio_assets.coin);
+
+    match item {
+        MainMenuItem::Continue => {
+            if let Some(save_game) = save::load_save() {
+                save::continue_run(&mut commands, save_game);
+                game_state.set(GameState::InGame).unwrap();
+            }
+        }
+        MainMenuItem::NewGame => {
+            game_state.set(GameState::InGame).unwrap();
+        }
+        MainMenuItem::EndlessMode => {
+            endless::start_endless_run(&mut commands, endless::random_seed(&time));
+            game_state.set(GameState::InGame).unwrap();
+        }
+        MainMenuItem::LevelSelect => {
+            game_state.push(GameState::LevelSelect).unwrap();
+        }
+        MainMenuItem::HighScores => {
+            game_state.push(GameState::Leaderboard).unwrap();
+        }
+        MainMenuItem::Settings => {
+            game_state.push(GameState::Settings).unwrap();
+        }
+        MainMenuItem::Credits => {
+            game_state.push(GameState::Credits).unwrap();
+        }
+        MainMenuItem::Quit => {
+            app_exit_events.send(AppExit);
+        }
     }
 }
 
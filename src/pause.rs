@@ -0,0 +1,197 @@
+use bevy::prelude::*;
+use heron::PhysicsTime;
+
+use crate::{
+    bitmap_text::{BitmapText, BitmapTextBundle, HEADING_SCALE},
+    input::Action,
+    levels::RestartLevel,
+    types::GameState,
+};
+
+const SELECTED_COLOR: Color = Color::WHITE;
+
+const UNSELECTED_COLOR: Color = Color::rgb(0.5, 0.5, 0.5);
+
+#[derive(Component, Default, Clone, Debug)]
+pub struct OnlyInPause;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PauseMenuItem {
+    Resume,
+    RestartLevel,
+    Settings,
+    QuitToMenu,
+}
+
+impl PauseMenuItem {
+    const ALL: [PauseMenuItem; 4] = [
+        PauseMenuItem::Resume,
+        PauseMenuItem::RestartLevel,
+        PauseMenuItem::Settings,
+        PauseMenuItem::QuitToMenu,
+    ];
+
+    fn label(&self) -> &'static str {
+        match self {
+            PauseMenuItem::Resume => "Resume",
+            PauseMenuItem::RestartLevel => "Restart Level",
+            PauseMenuItem::Settings => "Settings",
+            PauseMenuItem::QuitToMenu => "Quit to Menu",
+        }
+    }
+}
+
+#[derive(Component, Clone, Debug)]
+pub struct PauseMenuEntry(PauseMenuItem);
+
+/// Index into `PauseMenuItem::ALL` of the highlighted entry.
+#[derive(Default, Clone, Debug)]
+pub struct PauseMenuSelection(usize);
+
+pub struct PausePlugin;
+
+impl Plugin for PausePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PauseMenuSelection>()
+            .add_system_set(SystemSet::on_update(GameState::InGame).with_system(pause_game))
+            .add_system_set(
+                SystemSet::on_enter(GameState::Paused)
+                    .with_system(pause_physics)
+                    .with_system(spawn_ui),
+            )
+            .add_system_set(
+                SystemSet::on_update(GameState::Paused)
+                    .with_system(navigate_menu.label("navigate_pause_menu"))
+                    .with_system(highlight_selection.after("navigate_pause_menu"))
+                    .with_system(select_menu_item.after("navigate_pause_menu")),
+            )
+            // The settings menu is drawn on its own
+            .add_system_set(SystemSet::on_pause(GameState::Paused).with_system(cleanup))
+            .add_system_set(SystemSet::on_resume(GameState::Paused).with_system(spawn_ui))
+            .add_system_set(
+                SystemSet::on_exit(GameState::Paused)
+                    .with_system(resume_physics)
+                    .with_system(cleanup),
+            );
+    }
+}
+
+fn pause_game(mut actions: ResMut<Input<Action>>, mut game_state: ResMut<State<GameState>>) {
+    if actions.just_pressed(Action::Pause) {
+        // The paused state starts running this frame, so don't let it see the same press
+        actions.clear_just_pressed(Action::Pause);
+        game_state.push(GameState::Paused).unwrap();
+    }
+}
+
+fn pause_physics(mut physics_time: ResMut<PhysicsTime>, mut selection: ResMut<PauseMenuSelection>) {
+    physics_time.pause();
+    selection.0 = 0;
+}
+
+fn resume_physics(mut physics_time: ResMut<PhysicsTime>) {
+    physics_time.resume();
+}
+
+fn spawn_ui(mut commands: Commands) {
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                position_type: PositionType::Absolute,
+                flex_direction: FlexDirection::ColumnReverse,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..Default::default()
+            },
+            color: Color::rgba(0.098, 0.078, 0.169, 0.8).into(),
+            ..Default::default()
+        })
+        .insert(OnlyInPause)
+        .with_children(|parent| {
+            parent.spawn_bundle(BitmapTextBundle::new(
+                BitmapText::new("Paused")
+                    .with_color(Color::WHITE)
+                    .with_scale(HEADING_SCALE),
+            ));
+
+            for item in PauseMenuItem::ALL {
+                parent
+                    .spawn_bundle(BitmapTextBundle::new(
+                        BitmapText::new(item.label()).with_color(UNSELECTED_COLOR),
+                    ))
+                    .insert(PauseMenuEntry(item));
+            }
+        });
+}
+
+fn navigate_menu(actions: Res<Input<Action>>, mut selection: ResMut<PauseMenuSelection>) {
+    let item_count = PauseMenuItem::ALL.len();
+
+    if actions.just_pressed(Action::MoveDown) {
+        selection.0 = (selection.0 + 1) % item_count;
+    } else if actions.just_pressed(Action::MoveUp) {
+        selection.0 = (selection.0 + item_count - 1) % item_count;
+    }
+}
+
+fn highlight_selection(
+    selection: Res<PauseMenuSelection>,
+    mut query: Query<(&PauseMenuEntry, &mut BitmapText)>,
+) {
+    let selected = PauseMenuItem::ALL[selection.0];
+
+    for (entry, mut text) in query.iter_mut() {
+        let color = if entry.0 == selected {
+            SELECTED_COLOR
+        } else {
+            UNSELECTED_COLOR
+        };
+        // Every change lays the glyphs out again
+        if text.color != color {
+            text.color = color;
+        }
+    }
+}
+
+fn select_menu_item(
+    mut actions: ResMut<Input<Action>>,
+    selection: Res<PauseMenuSelection>,
+    mut game_state: ResMut<State<GameState>>,
+    mut restart_level_event: EventWriter<RestartLevel>,
+) {
+    if actions.just_pressed(Action::Pause) {
+        actions.clear_just_pressed(Action::Pause);
+        game_state.pop().unwrap();
+        return;
+    }
+
+    if !actions.just_released(Action::Confirm) {
+        return;
+    }
+
+    // Whichever state comes next starts running this frame, so don't let it see the same press
+    actions.clear_just_released(Action::Confirm);
+
+    match PauseMenuItem::ALL[selection.0] {
+        PauseMenuItem::Resume => {
+            game_state.pop().unwrap();
+        }
+        PauseMenuItem::RestartLevel => {
+            restart_level_event.send(RestartLevel);
+            game_state.pop().unwrap();
+        }
+        PauseMenuItem::Settings => {
+            game_state.push(GameState::Settings).unwrap();
+        }
+        PauseMenuItem::QuitToMenu => {
+            game_state.replace(GameState::MainMenu).unwrap();
+        }
+    }
+}
+
+fn cleanup(mut commands: Commands, query: Query<Entity, With<OnlyInPause>>) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
@@ -0,0 +1,290 @@
+use bevy::core_pipeline::{draw_2d_graph, node, RenderTargetClearColors, Transparent2d};
+use bevy::prelude::*;
+use bevy::render::camera::{ActiveCamera, Camera, CameraTypePlugin, RenderTarget};
+use bevy::render::render_graph::{Node, NodeRunError, RenderGraph, RenderGraphContext, SlotValue};
+use bevy::render::render_phase::RenderPhase;
+use bevy::render::render_resource::{
+    Extent3d, TextureDescriptor, TextureDimension, TextureFormat, TextureUsages,
+};
+use bevy::render::renderer::RenderContext;
+use bevy::render::texture::BevyDefault;
+use bevy::render::view::RenderLayers;
+use bevy::render::{RenderApp, RenderStage};
+use bevy::window::{WindowId, WindowMode};
+
+use crate::camera::CameraThatFollows;
+use crate::input::Action;
+
+/// World pixels shown when zoomed in. Every window shows exactly this much of the level, scaled
+/// up by a whole number and letterboxed.
+pub const VIEW_WIDTH: u32 = 320;
+pub const VIEW_HEIGHT: u32 = 180;
+
+/// Zooming out shows this many times as much of the level in each direction.
+pub const MAX_ZOOM_OUT: u32 = 2;
+
+/// The render graph node drawing the world into the low resolution target.
+const PIXEL_CAMERA_DRIVER: &str = "pixel_camera_driver";
+
+/// Everything that isn't in the world, only the upscaled image is drawn on this layer.
+const DISPLAY_LAYER: u8 = 1;
+
+#[derive(Clone, Debug)]
+pub struct VideoSettings {
+    /// 1 shows `VIEW_WIDTH` by `VIEW_HEIGHT` pixels, up to `MAX_ZOOM_OUT`
+    pub zoom_out: u32,
+    pub fullscreen: bool,
+}
+
+impl Default for VideoSettings {
+    fn default() -> Self {
+        Self {
+            zoom_out: 1,
+            fullscreen: false,
+        }
+    }
+}
+
+impl VideoSettings {
+    /// Size of the image the world is drawn into, one texel per world pixel.
+    pub fn view_size(&self) -> UVec2 {
+        UVec2::new(VIEW_WIDTH, VIEW_HEIGHT) * self.zoom_out.clamp(1, MAX_ZOOM_OUT)
+    }
+}
+
+/// The camera drawing the world, one world pixel to each texel of the target.
+#[derive(Component, Default)]
+pub struct PixelCamera;
+
+/// The sprite showing the world on the window.
+#[derive(Component, Default)]
+struct PixelDisplay;
+
+struct PixelTarget {
+    image: Handle<Image>,
+}
+
+pub struct PixelPerfectPlugin;
+
+impl Plugin for PixelPerfectPlugin {
+    fn build(&self, app: &mut App) {
+        // Multisampling would only smear the edges of the pixels
+        app.insert_resource(Msaa { samples: 1 })
+            .init_resource::<VideoSettings>()
+            .add_plugin(CameraTypePlugin::<PixelCamera>::default())
+            .add_startup_system(setup)
+            .add_system(toggle_zoom)
+            .add_system(toggle_fullscreen)
+            .add_system(
+                apply_video_settings
+                    .after(toggle_zoom)
+                    .after(toggle_fullscreen),
+            )
+            .add_system(fit_to_window.after(apply_video_settings));
+
+        // Nothing is drawn when running headless
+        let render_app = match app.get_sub_app_mut(RenderApp) {
+            Ok(render_app) => render_app,
+            Err(_) => return,
+        };
+
+        render_app.add_system_to_stage(RenderStage::Extract, extract_pixel_camera_phase);
+
+        let driver = PixelCameraDriver::new(&mut render_app.world);
+        let mut graph = render_app.world.resource_mut::<RenderGraph>();
+        graph.add_node(PIXEL_CAMERA_DRIVER, driver);
+
+        // Draw the world after clearing and before the main pass shows it on the window
+        graph
+            .add_node_edge(node::MAIN_PASS_DEPENDENCIES, PIXEL_CAMERA_DRIVER)
+            .unwrap();
+        graph
+            .add_node_edge(node::CLEAR_PASS_DRIVER, PIXEL_CAMERA_DRIVER)
+            .unwrap();
+        graph
+            .add_node_edge(PIXEL_CAMERA_DRIVER, node::MAIN_PASS_DRIVER)
+            .unwrap();
+    }
+}
+
+fn setup(
+    mut commands: Commands,
+    settings: Res<VideoSettings>,
+    clear_color: Res<ClearColor>,
+    mut clear_colors: ResMut<RenderTargetClearColors>,
+    mut images: ResMut<Assets<Image>>,
+) {
+    let size = settings.view_size();
+    let mut image = Image {
+        texture_descriptor: TextureDescriptor {
+            label: Some("pixel_target"),
+            size: Extent3d {
+                width: size.x,
+                height: size.y,
+                ..Default::default()
+            },
+            dimension: TextureDimension::D2,
+            format: TextureFormat::bevy_default(),
+            mip_level_count: 1,
+            sample_count: 1,
+            usage: TextureUsages::TEXTURE_BINDING
+                | TextureUsages::COPY_DST
+                | TextureUsages::RENDER_ATTACHMENT,
+        },
+        ..Default::default()
+    };
+    image.resize(image.texture_descriptor.size);
+    let image = images.add(image);
+
+    // The level keeps the background colour, the bars around it are black
+    let target = RenderTarget::Image(image.clone());
+    clear_colors.insert(target.clone(), clear_color.0);
+    clear_colors.insert(RenderTarget::Window(WindowId::primary()), Color::BLACK);
+
+    let camera = OrthographicCameraBundle::new_2d();
+    commands
+        .spawn_bundle(OrthographicCameraBundle {
+            camera: Camera {
+                target,
+                ..camera.camera
+            },
+            orthographic_projection: camera.orthographic_projection,
+            visible_entities: camera.visible_entities,
+            frustum: camera.frustum,
+            transform: camera.transform,
+            global_transform: camera.global_transform,
+            marker: PixelCamera,
+        })
+        .insert(CameraThatFollows::default());
+
+    commands
+        .spawn_bundle(OrthographicCameraBundle::new_2d())
+        .insert(RenderLayers::layer(DISPLAY_LAYER));
+
+    commands
+        .spawn_bundle(SpriteBundle {
+            texture: image.clone(),
+            ..Default::default()
+        })
+        .insert(RenderLayers::layer(DISPLAY_LAYER))
+        .insert(PixelDisplay);
+
+    commands.insert_resource(PixelTarget { image });
+}
+
+fn toggle_zoom(actions: Res<Input<Action>>, mut settings: ResMut<VideoSettings>) {
+    if actions.just_released(Action::ToggleZoom) {
+        settings.zoom_out = settings.zoom_out % MAX_ZOOM_OUT + 1;
+    }
+}
+
+fn toggle_fullscreen(actions: Res<Input<Action>>, mut settings: ResMut<VideoSettings>) {
+    if actions.just_pressed(Action::ToggleFullscreen) {
+        settings.fullscreen = !settings.fullscreen;
+    }
+}
+
+fn apply_video_settings(
+    settings: Res<VideoSettings>,
+    pixel_target: Res<PixelTarget>,
+    mut images: ResMut<Assets<Image>>,
+    mut windows: ResMut<Windows>,
+) {
+    if !settings.is_changed() {
+        return;
+    }
+
+    let size = settings.view_size();
+    if let Some(image) = images.get_mut(&pixel_target.image) {
+        let current = image.texture_descriptor.size;
+        if current.width != size.x || current.height != size.y {
+            image.resize(Extent3d {
+                width: size.x,
+                height: size.y,
+                ..Default::default()
+            });
+        }
+    }
+
+    if let Some(window) = windows.get_primary_mut() {
+        let mode = if settings.fullscreen {
+            WindowMode::BorderlessFullscreen
+        } else {
+            WindowMode::Windowed
+        };
+        if window.mode() != mode {
+            window.set_mode(mode);
+        }
+    }
+}
+
+/// Scales the world up by the largest whole number that fits the window, so every world pixel
+/// covers the same number of screen pixels.
+fn fit_to_window(
+    windows: Res<Windows>,
+    settings: Res<VideoSettings>,
+    mut query: Query<&mut Transform, With<PixelDisplay>>,
+) {
+    let window = match windows.get_primary() {
+        Some(window) => window,
+        None => return,
+    };
+
+    let view_size = settings.view_size();
+    let window_size = UVec2::new(window.physical_width(), window.physical_height());
+    let scale = (window_size / view_size).min_element().max(1);
+
+    // An odd number of leftover pixels would put every texel edge halfway across a pixel
+    let leftover = window_size.as_ivec2() - (view_size * scale).as_ivec2();
+    let offset = Vec2::new(
+        if leftover.x % 2 == 0 { 0. } else { 0.5 },
+        if leftover.y % 2 == 0 { 0. } else { 0.5 },
+    );
+
+    // The display camera works in logical pixels
+    let scale_factor = window.scale_factor() as f32;
+    for mut transform in query.iter_mut() {
+        transform.translation = (offset / scale_factor).extend(0.);
+        transform.scale = Vec3::new(scale as f32, scale as f32, 1.) / scale_factor;
+    }
+}
+
+fn extract_pixel_camera_phase(mut commands: Commands, active: Res<ActiveCamera<PixelCamera>>) {
+    if let Some(entity) = active.get() {
+        commands
+            .get_or_spawn(entity)
+            .insert(RenderPhase::<Transparent2d>::default());
+    }
+}
+
+/// Runs the regular 2D pass for the pixel camera.
+struct PixelCameraDriver {
+    query: QueryState<Entity, With<PixelCamera>>,
+}
+
+impl PixelCameraDriver {
+    fn new(render_world: &mut World) -> Self {
+        Self {
+            query: QueryState::new(render_world),
+        }
+    }
+}
+
+impl Node for PixelCameraDriver {
+    fn update(&mut self, world: &mut World) {
+        self.query.update_archetypes(world);
+    }
+
+    fn run(
+        &self,
+        graph: &mut RenderGraphContext,
+        _render_context: &mut RenderContext,
+        world: &World,
+    ) -> Result<(), NodeRunError> {
+        for camera in self.query.iter_manual(world) {
+            graph.run_sub_graph(draw_2d_graph::NAME, vec![SlotValue::Entity(camera)])?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,346 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use bevy::app::AppExit;
+use bevy::prelude::*;
+use bevy::utils::Instant;
+use heron::PhysicsSteps;
+use serde::{Deserialize, Serialize};
+
+use crate::endless::EndlessRun;
+use crate::game::game::GameWorldState;
+use crate::game::time::{GameTime, FIXED_STEP};
+use crate::input::{Action, MovementInput};
+use crate::levels::LevelState;
+use crate::save::{self, SaveGame};
+use crate::types::GameState;
+
+/// Bumped whenever the file layout or the rules it was played under change, older replays are
+/// refused instead of desyncing.
+const REPLAY_VERSION: u32 = 3;
+
+/// Stick movement is stored with this many steps per direction.
+const MOVEMENT_STEPS: f32 = 127.;
+
+/// A recorded run: where it started and the input of every frame after that.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Replay {
+    pub version: u32,
+    /// How far every frame advanced gameplay and physics
+    pub step: Duration,
+    pub start: SaveGame,
+    pub frames: Vec<RecordedFrames>,
+}
+
+impl Replay {
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let replay: Self = ron::from_str(&fs::read_to_string(path)?)?;
+
+        if replay.version != REPLAY_VERSION {
+            anyhow::bail!(
+                "replay version {} is not supported, expected {}",
+                replay.version,
+                REPLAY_VERSION
+            );
+        }
+
+        Ok(replay)
+    }
+
+    pub fn save(&self, path: &Path) -> anyhow::Result<()> {
+        fs::write(path, ron::to_string(self)?)?;
+        Ok(())
+    }
+
+    fn push(&mut self, input: FrameInput) {
+        match self.frames.last_mut() {
+            Some(last) if last.input == input => last.count += 1,
+            _ => self.frames.push(RecordedFrames { count: 1, input }),
+        }
+    }
+
+    fn frame_count(&self) -> u32 {
+        self.frames.iter().map(|frames| frames.count).sum()
+    }
+}
+
+/// Consecutive frames with identical input, which keeps replays of long runs small.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct RecordedFrames {
+    pub count: u32,
+    pub input: FrameInput,
+}
+
+/// Which actions were held during a frame and where the player was steering.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FrameInput {
+    actions: u16,
+    movement: (i8, i8),
+}
+
+impl FrameInput {
+    fn capture(actions: &Input<Action>, movement: &MovementInput) -> Self {
+        let quantize = |value: f32| (value * MOVEMENT_STEPS).round() as i8;
+
+        Self {
+            actions: Action::ALL
+                .iter()
+                .enumerate()
+                .filter(|(_, action)| actions.pressed(**action))
+                .fold(0, |bits, (index, _)| bits | 1 << index),
+            movement: (quantize(movement.0.x), quantize(movement.0.y)),
+        }
+    }
+
+    fn pressed(&self, action: Action) -> bool {
+        let index = Action::ALL.iter().position(|other| *other == action);
+        index.map_or(false, |index| self.actions & 1 << index != 0)
+    }
+
+    /// Overwrites this frame's input. Presses and releases are derived from the previous frame,
+    /// so both recording and playback see exactly the same `just_pressed` and `just_released`.
+    fn apply(
+        &self,
+        previous: &FrameInput,
+        actions: &mut Input<Action>,
+        movement: &mut MovementInput,
+    ) {
+        for action in Action::ALL {
+            let was_pressed = previous.pressed(action);
+            let is_pressed = self.pressed(action);
+
+            actions.reset(action);
+            if was_pressed {
+                actions.press(action);
+                actions.clear_just_pressed(action);
+            }
+
+            if is_pressed && !was_pressed {
+                actions.press(action);
+            } else if !is_pressed && was_pressed {
+                actions.release(action);
+            }
+        }
+
+        movement.0 = Vec2::new(
+            self.movement.0 as f32 / MOVEMENT_STEPS,
+            self.movement.1 as f32 / MOVEMENT_STEPS,
+        );
+    }
+}
+
+/// Records every run to the file, overwriting it each time a new run starts.
+pub struct ReplayRecorder {
+    path: PathBuf,
+    replay: Option<Replay>,
+    previous: FrameInput,
+}
+
+/// Plays a replay from the main menu, then hands control back to the player.
+pub struct ReplayPlayer {
+    replay: Replay,
+    started: bool,
+    /// Index into `replay.frames` and how many frames of that entry have been played
+    cursor: (usize, u32),
+    previous: FrameInput,
+}
+
+impl ReplayPlayer {
+    fn next_input(&mut self) -> Option<FrameInput> {
+        let (index, played) = &mut self.cursor;
+        let frames = self.replay.frames.get(*index)?;
+
+        *played += 1;
+        if *played >= frames.count {
+            *index += 1;
+            *played = 0;
+        }
+
+        Some(frames.input)
+    }
+}
+
+/// Either records runs to `record` or plays back the replay at `replay`, as chosen on the
+/// command line. Both run gameplay and physics on a fixed step.
+#[derive(Clone, Debug, Default)]
+pub struct ReplayPlugin {
+    pub record: Option<PathBuf>,
+    pub replay: Option<PathBuf>,
+}
+
+impl Plugin for ReplayPlugin {
+    fn build(&self, app: &mut App) {
+        let step = if let Some(path) = &self.replay {
+            match Replay::load(path) {
+                Ok(replay) => {
+                    info!(
+                        "Playing {} frames from {}",
+                        replay.frame_count(),
+                        path.display()
+                    );
+
+                    let step = replay.step;
+                    app.insert_resource(ReplayPlayer {
+                        replay,
+                        started: false,
+                        cursor: (0, 0),
+                        previous: FrameInput::default(),
+                    })
+                    .add_system_set(
+                        SystemSet::on_update(GameState::MainMenu).with_system(start_playback),
+                    )
+                    .add_system_to_stage(CoreStage::PreUpdate, play_input.after("update_actions"));
+                    step
+                }
+                Err(error) => {
+                    error!("Failed to load replay {}: {}", path.display(), error);
+                    return;
+                }
+            }
+        } else if let Some(path) = &self.record {
+            app.insert_resource(ReplayRecorder {
+                path: path.clone(),
+                replay: None,
+                previous: FrameInput::default(),
+            })
+            .add_system_to_stage(CoreStage::PreUpdate, record_input.after("update_actions"))
+            .add_system_to_stage(CoreStage::Last, save_recording_on_exit);
+            FIXED_STEP
+        } else {
+            return;
+        };
+
+        app.insert_resource(GameTime::fixed(step))
+            .insert_resource(PhysicsSteps::every_frame(step))
+            .insert_resource(FrameLimit(step))
+            .add_system_to_stage(CoreStage::Last, limit_frame_rate);
+    }
+}
+
+/// Settings opened from the pause menu still count, the run is underneath them.
+fn is_playing(game_state: &State<GameState>) -> bool {
+    matches!(game_state.current(), GameState::InGame | GameState::Paused)
+        || game_state.inactives().contains(&GameState::InGame)
+}
+
+fn record_input(
+    game_state: Res<State<GameState>>,
+    level_state: Res<LevelState>,
+    game_world_state: Res<GameWorldState>,
+    endless_run: Option<Res<EndlessRun>>,
+    mut recorder: ResMut<ReplayRecorder>,
+    mut actions: ResMut<Input<Action>>,
+    mut movement: ResMut<MovementInput>,
+) {
+    if !is_playing(&game_state) {
+        if let Some(replay) = recorder.replay.take() {
+            save_recording(&recorder.path, &replay);
+        }
+        return;
+    }
+
+    let recorder = &mut *recorder;
+    if recorder.replay.is_none() {
+        recorder.previous = FrameInput::default();
+        recorder.replay = Some(Replay {
+            version: REPLAY_VERSION,
+            step: FIXED_STEP,
+            start: SaveGame {
+                version: save::SAVE_VERSION,
+                current_level: level_state.current_level,
+                world: game_world_state.clone(),
+                endless: endless_run.map(|endless_run| *endless_run),
+            },
+            frames: Vec::new(),
+        });
+    }
+
+    // Feed the game the recorded input rather than the live one, so playback can't differ
+    let input = FrameInput::capture(&actions, &movement);
+    input.apply(&recorder.previous, &mut actions, &mut movement);
+    recorder.previous = input;
+
+    if let Some(replay) = &mut recorder.replay {
+        replay.push(input);
+    }
+}
+
+fn save_recording_on_exit(
+    mut app_exit_events: EventReader<AppExit>,
+    mut recorder: ResMut<ReplayRecorder>,
+) {
+    if app_exit_events.iter().next().is_none() {
+        return;
+    }
+
+    if let Some(replay) = recorder.replay.take() {
+        save_recording(&recorder.path, &replay);
+    }
+}
+
+fn save_recording(path: &Path, replay: &Replay) {
+    match replay.save(path) {
+        Ok(()) => info!(
+            "Recorded {} frames to {}",
+            replay.frame_count(),
+            path.display()
+        ),
+        Err(error) => error!("Failed to write replay {}: {}", path.display(), error),
+    }
+}
+
+fn start_playback(
+    mut commands: Commands,
+    mut player: ResMut<ReplayPlayer>,
+    mut game_state: ResMut<State<GameState>>,
+) {
+    if player.started {
+        // The replay returned to the menu by itself
+        commands.remove_resource::<ReplayPlayer>();
+        return;
+    }
+
+    player.started = true;
+    save::continue_run(&mut commands, player.replay.start.clone());
+    game_state.set(GameState::InGame).unwrap();
+}
+
+fn play_input(
+    mut commands: Commands,
+    game_state: Res<State<GameState>>,
+    player: Option<ResMut<ReplayPlayer>>,
+    mut actions: ResMut<Input<Action>>,
+    mut movement: ResMut<MovementInput>,
+) {
+    let mut player = match player {
+        Some(player) if player.started && is_playing(&game_state) => player,
+        _ => return,
+    };
+
+    match player.next_input() {
+        Some(input) => {
+            input.apply(&player.previous, &mut actions, &mut movement);
+            player.previous = input;
+        }
+        None => {
+            info!("Replay finished");
+            commands.remove_resource::<ReplayPlayer>();
+        }
+    }
+}
+
+/// The shortest time a frame may take while recording or playing back.
+struct FrameLimit(Duration);
+
+/// Keeps fixed step runs at real time speed on fast displays by sleeping off the rest of the frame.
+fn limit_frame_rate(frame_limit: Res<FrameLimit>, mut last_frame: Local<Option<Instant>>) {
+    if let Some(last_frame) = *last_frame {
+        let elapsed = last_frame.elapsed();
+        if elapsed < frame_limit.0 {
+            std::thread::sleep(frame_limit.0 - elapsed);
+        }
+    }
+
+    *last_frame = Some(Instant::now());
+}
@@ -0,0 +1,102 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    endless::EndlessRun,
+    game::game::{GameWorldState, HEALTH_PER_HEART},
+    levels::{IncrementLevel, LevelState},
+    storage,
+    types::GameState,
+};
+
+const SAVE_KEY: &str = "save";
+
+/// Bumped whenever older saves need converting. Version 1 counts health in half hearts.
+pub const SAVE_VERSION: u32 = 1;
+
+/// Everything needed to pick a run back up at the start of a level.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SaveGame {
+    /// Saves from before versions were written count as 0
+    #[serde(default)]
+    pub version: u32,
+    pub current_level: usize,
+    pub world: GameWorldState,
+    /// Set for runs through generated floors
+    #[serde(default)]
+    pub endless: Option<EndlessRun>,
+}
+
+/// Inserted before entering `GameState::InGame` to resume a run instead of starting over.
+#[derive(Clone, Debug)]
+pub struct ContinueRun(pub SaveGame);
+
+pub struct SavePlugin;
+
+impl Plugin for SavePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(
+            SystemSet::on_update(GameState::InGame).with_system(autosave.after("change_level")),
+        )
+        .add_system_set(SystemSet::on_enter(GameState::GameOver).with_system(delete_save))
+        .add_system_set(SystemSet::on_enter(GameState::Victory).with_system(delete_save));
+    }
+}
+
+/// Sets up the next `GameState::InGame` to resume the run, in endless mode if it was one.
+pub fn continue_run(commands: &mut Commands, save_game: SaveGame) {
+    match save_game.endless {
+        Some(endless_run) => commands.insert_resource(endless_run),
+        None => commands.remove_resource::<EndlessRun>(),
+    }
+
+    commands.insert_resource(ContinueRun(save_game));
+}
+
+pub fn has_save() -> bool {
+    storage::exists(SAVE_KEY)
+}
+
+pub fn load_save() -> Option<SaveGame> {
+    storage::load(SAVE_KEY).map(SaveGame::upgrade)
+}
+
+impl SaveGame {
+    /// Converts a save written by an older version of the game.
+    fn upgrade(mut self) -> Self {
+        if self.version < 1 {
+            self.world.player_health *= HEALTH_PER_HEART;
+            self.world.max_health *= HEALTH_PER_HEART;
+        }
+
+        self.version = SAVE_VERSION;
+        self
+    }
+}
+
+/// Saves every time the player takes the stairs, so continuing starts at the newly reached level.
+fn autosave(
+    mut increment_level_event: EventReader<IncrementLevel>,
+    level_state: Res<LevelState>,
+    game_world_state: Res<GameWorldState>,
+    endless_run: Option<Res<EndlessRun>>,
+) {
+    if increment_level_event.iter().count() == 0 {
+        return;
+    }
+
+    storage::save(
+        SAVE_KEY,
+        &SaveGame {
+            version: SAVE_VERSION,
+            current_level: level_state.current_level,
+            world: game_world_state.clone(),
+            endless: endless_run.map(|endless_run| *endless_run),
+        },
+    );
+}
+
+/// A finished run can't be continued.
+fn delete_save() {
+    storage::remove(SAVE_KEY);
+}
@@ -0,0 +1,369 @@
+use bevy::audio::AudioSink;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    bitmap_text::{BitmapText, BitmapTextBundle, HEADING_SCALE},
+    camera::CameraSettings,
+    input::Action,
+    pixel_perfect::{VideoSettings, MAX_ZOOM_OUT},
+    replay::ReplayPlayer,
+    storage,
+    types::GameState,
+};
+
+const SETTINGS_KEY: &str = "settings";
+
+const VOLUME_STEP: f32 = 0.1;
+
+/// Characters in a full volume bar.
+const VOLUME_BAR_LENGTH: usize = 10;
+
+const SELECTED_COLOR: Color = Color::WHITE;
+
+const UNSELECTED_COLOR: Color = Color::rgb(0.5, 0.5, 0.5);
+
+/// Options picked in the settings menu. Stored in the `settings` config so they are kept
+/// between runs.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    /// From 0 to 1
+    pub music_volume: f32,
+    /// From 0 to 1
+    pub sfx_volume: f32,
+    /// Silences everything without losing the volumes
+    pub muted: bool,
+    pub fullscreen: bool,
+    /// The zoom every run starts with, `ToggleZoom` changes it for the moment
+    pub zoom_out: u32,
+    pub screen_shake: bool,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            music_volume: 0.7,
+            sfx_volume: 1.,
+            muted: false,
+            fullscreen: false,
+            zoom_out: 1,
+            screen_shake: true,
+        }
+    }
+}
+
+impl Settings {
+    pub fn load() -> Self {
+        storage::load(SETTINGS_KEY).unwrap_or_default()
+    }
+
+    pub fn save(&self) {
+        storage::save(SETTINGS_KEY, self);
+    }
+
+    pub fn music_volume(&self) -> f32 {
+        if self.muted {
+            0.
+        } else {
+            self.music_volume
+        }
+    }
+
+    pub fn sfx_volume(&self) -> f32 {
+        if self.muted {
+            0.
+        } else {
+            self.sfx_volume
+        }
+    }
+}
+
+/// The looping background music, kept to change its volume while it plays.
+pub struct BackgroundMusic(pub Handle<AudioSink>);
+
+#[derive(Component, Default, Clone, Debug)]
+pub struct OnlyInSettings;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SettingsMenuItem {
+    MusicVolume,
+    SfxVolume,
+    Mute,
+    WindowMode,
+    Zoom,
+    ScreenShake,
+    Back,
+}
+
+impl SettingsMenuItem {
+    const ALL: [SettingsMenuItem; 7] = [
+        SettingsMenuItem::MusicVolume,
+        SettingsMenuItem::SfxVolume,
+        SettingsMenuItem::Mute,
+        SettingsMenuItem::WindowMode,
+        SettingsMenuItem::Zoom,
+        SettingsMenuItem::ScreenShake,
+        SettingsMenuItem::Back,
+    ];
+
+    fn label(&self, settings: &Settings) -> String {
+        let on_off = |on: bool| if on { "On" } else { "Off" };
+
+        match self {
+            SettingsMenuItem::MusicVolume => {
+                format!("Music  {}", volume_bar(settings.music_volume))
+            }
+            SettingsMenuItem::SfxVolume => format!("Sounds {}", volume_bar(settings.sfx_volume)),
+            SettingsMenuItem::Mute => format!("Mute: {}", on_off(settings.muted)),
+            SettingsMenuItem::WindowMode => {
+                let mode = if settings.fullscreen {
+                    "Fullscreen"
+                } else {
+                    "Windowed"
+                };
+                format!("Window: {}", mode)
+            }
+            SettingsMenuItem::Zoom => format!("Zoom: {}x", settings.zoom_out),
+            SettingsMenuItem::ScreenShake => {
+                format!("Screen Shake: {}", on_off(settings.screen_shake))
+            }
+            SettingsMenuItem::Back => "Back".to_string(),
+        }
+    }
+
+    /// Applies left and right, given as -1 or 1. Toggles ignore the direction.
+    fn adjust(&self, settings: &mut Settings, direction: i32) {
+        match self {
+            SettingsMenuItem::MusicVolume => {
+                settings.music_volume = step_volume(settings.music_volume, direction)
+            }
+            SettingsMenuItem::SfxVolume => {
+                settings.sfx_volume = step_volume(settings.sfx_volume, direction)
+            }
+            SettingsMenuItem::Mute => settings.muted = !settings.muted,
+            SettingsMenuItem::WindowMode => settings.fullscreen = !settings.fullscreen,
+            SettingsMenuItem::Zoom => {
+                settings.zoom_out =
+                    (settings.zoom_out as i32 + direction).clamp(1, MAX_ZOOM_OUT as i32) as u32
+            }
+            SettingsMenuItem::ScreenShake => settings.screen_shake = !settings.screen_shake,
+            SettingsMenuItem::Back => {}
+        }
+    }
+}
+
+fn step_volume(volume: f32, direction: i32) -> f32 {
+    // Rounding keeps repeated steps from drifting away from whole tenths
+    let steps = (volume / VOLUME_STEP).round() + direction as f32;
+    (steps * VOLUME_STEP).clamp(0., 1.)
+}
+
+fn volume_bar(volume: f32) -> String {
+    let filled = (volume * VOLUME_BAR_LENGTH as f32).round() as usize;
+    format!(
+        "{}{} {:>3}%",
+        "=".repeat(filled),
+        "-".repeat(VOLUME_BAR_LENGTH - filled),
+        (volume * 100.).round()
+    )
+}
+
+#[derive(Component, Clone, Debug)]
+pub struct SettingsMenuEntry(SettingsMenuItem);
+
+/// Index into `SettingsMenuItem::ALL` of the highlighted entry.
+#[derive(Default, Clone, Debug)]
+pub struct SettingsMenuSelection(usize);
+
+pub struct SettingsPlugin;
+
+impl Plugin for SettingsPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Settings::load())
+            .init_resource::<SettingsMenuSelection>()
+            .add_system(apply_settings)
+            .add_system(remember_fullscreen.after(apply_settings))
+            .add_system(save_settings)
+            .add_system_set(
+                SystemSet::on_enter(GameState::Settings)
+                    .with_system(reset_selection)
+                    .with_system(spawn_ui),
+            )
+            .add_system_set(
+                SystemSet::on_update(GameState::Settings)
+                    .with_system(navigate_menu.label("navigate_settings"))
+                    .with_system(
+                        change_setting
+                            .label("change_setting")
+                            .after("navigate_settings"),
+                    )
+                    .with_system(update_entries.after("change_setting")),
+            )
+            .add_system_set(SystemSet::on_exit(GameState::Settings).with_system(cleanup));
+    }
+}
+
+/// Passes the settings on to everything they affect whenever they change.
+fn apply_settings(
+    settings: Res<Settings>,
+    mut applied: Local<Option<Settings>>,
+    mut video_settings: ResMut<VideoSettings>,
+    mut camera_settings: ResMut<CameraSettings>,
+    audio_sinks: Res<Assets<AudioSink>>,
+    background_music: Option<Res<BackgroundMusic>>,
+) {
+    // Checking the music every frame catches it once it has started playing
+    if let Some(sink) = background_music.and_then(|music| audio_sinks.get(&music.0)) {
+        if sink.volume() != settings.music_volume() {
+            sink.set_volume(settings.music_volume());
+        }
+    }
+
+    if !settings.is_changed() {
+        return;
+    }
+
+    // Only a new default zoom replaces the one picked with `ToggleZoom`
+    let previous = applied.replace(settings.clone());
+    if previous.map_or(true, |previous| previous.zoom_out != settings.zoom_out) {
+        video_settings.zoom_out = settings.zoom_out;
+    }
+    if video_settings.fullscreen != settings.fullscreen {
+        video_settings.fullscreen = settings.fullscreen;
+    }
+    camera_settings.screen_shake = settings.screen_shake;
+}
+
+/// Going fullscreen with `ToggleFullscreen` sticks like picking it in the menu.
+fn remember_fullscreen(video_settings: Res<VideoSettings>, mut settings: ResMut<Settings>) {
+    if video_settings.is_changed()
+        && !video_settings.is_added()
+        && settings.fullscreen != video_settings.fullscreen
+    {
+        settings.fullscreen = video_settings.fullscreen;
+    }
+}
+
+/// Replays don't touch the viewer's saved settings, even when the recorded run toggled fullscreen.
+fn save_settings(settings: Res<Settings>, replay_player: Option<Res<ReplayPlayer>>) {
+    if settings.is_changed() && !settings.is_added() && replay_player.is_none() {
+        settings.save();
+    }
+}
+
+fn reset_selection(mut selection: ResMut<SettingsMenuSelection>) {
+    selection.0 = 0;
+}
+
+fn spawn_ui(mut commands: Commands, settings: Res<Settings>) {
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                position_type: PositionType::Absolute,
+                flex_direction: FlexDirection::ColumnReverse,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..Default::default()
+            },
+            color: Color::rgb(0.098, 0.078, 0.169).into(),
+            ..Default::default()
+        })
+        .insert(OnlyInSettings)
+        .with_children(|parent| {
+            parent.spawn_bundle(BitmapTextBundle::new(
+                BitmapText::new("Settings")
+                    .with_color(Color::WHITE)
+                    .with_scale(HEADING_SCALE),
+            ));
+
+            for item in SettingsMenuItem::ALL {
+                parent
+                    .spawn_bundle(BitmapTextBundle::new(
+                        BitmapText::new(item.label(&settings)).with_color(UNSELECTED_COLOR),
+                    ))
+                    .insert(SettingsMenuEntry(item));
+            }
+
+            parent.spawn_bundle(BitmapTextBundle::new(
+                BitmapText::new("Left and right to change").with_color(UNSELECTED_COLOR),
+            ));
+        });
+}
+
+fn navigate_menu(actions: Res<Input<Action>>, mut selection: ResMut<SettingsMenuSelection>) {
+    let item_count = SettingsMenuItem::ALL.len();
+
+    if actions.just_pressed(Action::MoveDown) {
+        selection.0 = (selection.0 + 1) % item_count;
+    } else if actions.just_pressed(Action::MoveUp) {
+        selection.0 = (selection.0 + item_count - 1) % item_count;
+    }
+}
+
+/// A replay that opened the settings can still move through them and leave, but changes nothing.
+fn change_setting(
+    mut actions: ResMut<Input<Action>>,
+    selection: Res<SettingsMenuSelection>,
+    mut settings: ResMut<Settings>,
+    mut game_state: ResMut<State<GameState>>,
+    replay_player: Option<Res<ReplayPlayer>>,
+) {
+    let selected = SettingsMenuItem::ALL[selection.0];
+
+    if actions.just_pressed(Action::Pause)
+        || (selected == SettingsMenuItem::Back && actions.just_released(Action::Confirm))
+    {
+        // The menu underneath resumes this frame, so don't let it see the same press
+        actions.clear_just_pressed(Action::Pause);
+        actions.clear_just_released(Action::Confirm);
+        game_state.pop().unwrap();
+        return;
+    }
+
+    let direction = if actions.just_pressed(Action::MoveRight) {
+        1
+    } else if actions.just_pressed(Action::MoveLeft) {
+        -1
+    } else if actions.just_released(Action::Confirm) {
+        // Confirm flips toggles and turns volumes up
+        1
+    } else {
+        return;
+    };
+
+    if replay_player.is_none() {
+        selected.adjust(&mut settings, direction);
+    }
+}
+
+fn update_entries(
+    settings: Res<Settings>,
+    selection: Res<SettingsMenuSelection>,
+    mut query: Query<(&SettingsMenuEntry, &mut BitmapText)>,
+) {
+    let selected = SettingsMenuItem::ALL[selection.0];
+
+    for (entry, mut text) in query.iter_mut() {
+        let label = entry.0.label(&settings);
+        if text.value != label {
+            text.value = label;
+        }
+
+        let color = if entry.0 == selected {
+            SELECTED_COLOR
+        } else {
+            UNSELECTED_COLOR
+        };
+        if text.color != color {
+            text.color = color;
+        }
+    }
+}
+
+fn cleanup(mut commands: Commands, query: Query<Entity, With<OnlyInSettings>>) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
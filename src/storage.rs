@@ -0,0 +1,123 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use bevy::prelude::*;
+use serde::{de::DeserializeOwned, Serialize};
+
+/// Set by `disable`, nothing is read or written afterwards.
+static DISABLED: AtomicBool = AtomicBool::new(false);
+
+/// Turns storage off for the rest of the process. Headless runs use this so they play the same
+/// on every machine, whatever its settings, bindings and saves, and don't write any files.
+pub fn disable() {
+    DISABLED.store(true, Ordering::Relaxed);
+}
+
+fn is_disabled() -> bool {
+    DISABLED.load(Ordering::Relaxed)
+}
+
+/// Reads and deserializes the value stored under the key, if there is a valid one.
+pub fn load<T: DeserializeOwned>(key: &str) -> Option<T> {
+    if is_disabled() {
+        return None;
+    }
+
+    let contents = backend::read(key)?;
+
+    match ron::from_str(&contents) {
+        Ok(value) => Some(value),
+        Err(error) => {
+            warn!("Ignoring unreadable {}: {}", key, error);
+            None
+        }
+    }
+}
+
+pub fn save<T: Serialize>(key: &str, value: &T) {
+    if is_disabled() {
+        return;
+    }
+
+    match ron::to_string(value) {
+        Ok(contents) => backend::write(key, &contents),
+        Err(error) => error!("Failed to serialize {}: {}", key, error),
+    }
+}
+
+pub fn exists(key: &str) -> bool {
+    !is_disabled() && backend::read(key).is_some()
+}
+
+pub fn remove(key: &str) {
+    if !is_disabled() {
+        backend::remove(key);
+    }
+}
+
+// Native builds keep one file per key in the `saves` directory
+#[cfg(not(target_arch = "wasm32"))]
+mod backend {
+    use std::{fs, path::PathBuf};
+
+    use bevy::prelude::*;
+
+    const SAVE_DIRECTORY: &str = "saves";
+
+    fn path(key: &str) -> PathBuf {
+        PathBuf::from(SAVE_DIRECTORY).join(format!("{}.ron", key))
+    }
+
+    pub fn read(key: &str) -> Option<String> {
+        fs::read_to_string(path(key)).ok()
+    }
+
+    pub fn write(key: &str, contents: &str) {
+        let result =
+            fs::create_dir_all(SAVE_DIRECTORY).and_then(|_| fs::write(path(key), contents));
+
+        if let Err(error) = result {
+            error!("Failed to write {}: {}", key, error);
+        }
+    }
+
+    pub fn remove(key: &str) {
+        fs::remove_file(path(key)).ok();
+    }
+}
+
+// The web build keeps them in the browser's localStorage
+#[cfg(target_arch = "wasm32")]
+mod backend {
+    use bevy::prelude::*;
+    use web_sys::Storage;
+
+    const KEY_PREFIX: &str = "obscure-resurrection";
+
+    fn local_storage() -> Option<Storage> {
+        web_sys::window()?.local_storage().ok().flatten()
+    }
+
+    fn prefixed(key: &str) -> String {
+        format!("{}/{}", KEY_PREFIX, key)
+    }
+
+    pub fn read(key: &str) -> Option<String> {
+        local_storage()?.get_item(&prefixed(key)).ok().flatten()
+    }
+
+    pub fn write(key: &str, contents: &str) {
+        let written = local_storage()
+            .map(|storage| storage.set_item(&prefixed(key), contents).is_ok())
+            .unwrap_or(false);
+
+        if !written {
+            error!("Failed to write {} to localStorage", key);
+        }
+    }
+
+    pub fn remove(key: &str) {
+        if let Some(storage) = local_storage() {
+            storage.remove_item(&prefixed(key)).ok();
+        }
+    }
+}
@@ -2,12 +2,20 @@ use bevy::prelude::*;
 use bevy_asset_loader::AssetCollection;
 use bevy_ecs_ldtk::LdtkAsset;
 
+use crate::game::enemy::kinds::EnemyKinds;
+
 #[derive(Debug, Clone, Eq, PartialEq, Hash)]
 pub enum GameState {
     Loading,
     MainMenu,
     InGame,
+    Paused,
     GameOver,
+    Victory,
+    Leaderboard,
+    LevelSelect,
+    Settings,
+    Credits,
 }
 
 #[derive(AssetCollection, Clone)]
@@ -30,41 +38,20 @@ pub struct ImageAssets {
     #[asset(path = "icons/empty_heart_container.png")]
     pub empty_heart: Handle<Image>,
 
+    #[asset(path = "icons/half_heart.png")]
+    pub half_heart: Handle<Image>,
+
     #[asset(path = "icons/potion.png")]
     pub potion: Handle<Image>,
 
     #[asset(path = "icons/coin.png")]
     pub coin: Handle<Image>,
 
-    #[asset(path = "text/tile-0.png")]
-    pub text0: Handle<Image>,
-
-    #[asset(path = "text/tile-1.png")]
-    pub text1: Handle<Image>,
-
-    #[asset(path = "text/tile-2.png")]
-    pub text2: Handle<Image>,
-
-    #[asset(path = "text/tile-3.png")]
-    pub text3: Handle<Image>,
-
-    #[asset(path = "text/tile-4.png")]
-    pub text4: Handle<Image>,
-
-    #[asset(path = "text/tile-5.png")]
-    pub text5: Handle<Image>,
+    #[asset(path = "oracle_1bit_assets/alphanumeric.png")]
+    pub alphanumeric: Handle<Image>,
 
-    #[asset(path = "text/tile-6.png")]
-    pub text6: Handle<Image>,
-
-    #[asset(path = "text/tile-7.png")]
-    pub text7: Handle<Image>,
-
-    #[asset(path = "text/tile-8.png")]
-    pub text8: Handle<Image>,
-
-    #[asset(path = "text/tile-9.png")]
-    pub text9: Handle<Image>,
+    #[asset(path = "oracle_1bit_assets/dialogue.png")]
+    pub dialogue: Handle<Image>,
 }
 
 #[derive(AssetCollection)]
@@ -85,6 +72,8 @@ pub struct AudioAssets {
     pub bork: Handle<AudioSource>,
 }
 
+/// Only the level problems listed in debug builds still use a TTF font, they quote file contents
+/// with characters `alphanumeric.png` doesn't have. Everything else draws `BitmapText`.
 #[derive(AssetCollection)]
 pub struct FontAssets {
     #[asset(path = "ConsolaMono.ttf")]
@@ -96,3 +85,9 @@ pub struct LevelAssets {
     #[asset(path = "levels.ldtk")]
     pub levels: Handle<LdtkAsset>,
 }
+
+#[derive(AssetCollection)]
+pub struct EnemyAssets {
+    #[asset(path = "kinds.enemies.ron")]
+    pub kinds: Handle<EnemyKinds>,
+}